@@ -1,88 +1,404 @@
-use std::net::Ipv4Addr;
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, TcpStream};
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::Duration;
-use adb_client::{AdbTcpConnection, RustADBError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use log::{info, warn};
-use tauri::State;
-use crate::structs::{LocalDevice, Paths, ZBBError};
-use crate::util::create_silent_command;
+use tauri::{AppHandle, Manager, State};
+use crate::operations::Operations;
+use crate::client::AdbClient;
+use crate::registry::Registry;
+use crate::structs::{AdbServer, AppActivity, AppState, BatteryStatus, ControllerStatus, Hand, InstallProgress, InstallStage, KnownAddress, LocalDevice, LocalDeviceLong, LocalDeviceState, PackageInfo, Paths, PowerSource, RebootMode, ReconnectAttempt, WifiStatus, ZBBError};
+use crate::util::{create_silent_command, load_json, save_json};
 
 
-const LOOPBACK: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
-const ADB_PORT: u16 = 5037;
+pub const LOOPBACK: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
+pub const ADB_PORT: u16 = 5037;
+
+/// Emitted with the full `Vec<LocalDevice>` whenever the device list changes.
+pub const DEVICES_CHANGED_EVENT: &str = "devices-changed";
+/// Emitted with an [InstallProgress] for every step of an install or uninstall.
+pub const INSTALL_PROGRESS_EVENT: &str = "install-progress";
+const REMOTE_APK_PATH: &str = "/data/local/tmp/zbbvrui_install.apk";
+const TRACKER_RETRY_DELAY: Duration = Duration::from_millis(1000);
+/// File in the app data dir that holds the configured [AdbServer].
+const ADB_SERVER_FILE: &str = "adb_server.json";
+/// Emitted with a [ReconnectAttempt] whenever the supervisor tries to reconnect a device.
+pub const RECONNECT_EVENT: &str = "device-reconnect";
+const SUPERVISOR_INTERVAL: Duration = Duration::from_millis(2000);
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(2);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+const REBOOT_POLL_INTERVAL: Duration = Duration::from_millis(1000);
+/// Time a rebooting device gets to drop off the device list before we wait for it to come back.
+const REBOOT_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+// #####################
+// # SHELL COMMANDS    # 
+// #####################
+
+/// Builds the arguments of an `adb shell` invocation.
+///
+/// The ADB server joins all arguments with spaces and hands them to the device's shell,
+/// so every argument is quoted here. Package and component names coming from the
+/// settings are validated on top of that.
+#[derive(Debug, Clone)]
+pub struct ShellCommand {
+    args: Vec<String>,
+}
+
+impl ShellCommand {
+    pub fn new(program: &str) -> Self {
+        Self {
+            args: vec![quote(program)],
+        }
+    }
+
+    pub fn arg(mut self, arg: impl AsRef<str>) -> Self {
+        self.args.push(quote(arg.as_ref()));
+        self
+    }
+
+    /// Adds a package name like `ch.sbb.xr.zbbvr`.
+    pub fn package(self, package: &str) -> Result<Self, ZBBError> {
+        if !is_valid_package(package) {
+            return Err(ZBBError::InvalidArgument(format!("Invalid package name: {}", package)));
+        }
+
+        Ok(self.arg(package))
+    }
+
+    /// Adds a component name like `ch.sbb.xr.zbbvr/.MainActivity`.
+    pub fn component(self, component: &str) -> Result<Self, ZBBError> {
+        if !is_valid_component(component) {
+            return Err(ZBBError::InvalidArgument(format!("Invalid activity name: {}", component)));
+        }
+
+        Ok(self.arg(component))
+    }
+
+    pub fn build(self) -> Vec<String> {
+        self.args
+    }
+}
+
+/// Quotes [arg] for a POSIX shell, leaving plain words untouched for readable logs.
+fn quote(arg: &str) -> String {
+    let is_plain = !arg.is_empty()
+        && arg.chars().all(|c| c.is_ascii_alphanumeric() || "_-.,:/=@%+".contains(c));
+
+    if is_plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Package names consist of at least two dot-separated segments, each starting with a letter
+/// and continuing with letters, digits or underscores.
+fn is_valid_package(package: &str) -> bool {
+    let segments = package.split('.').collect::<Vec<_>>();
+
+    segments.len() >= 2
+        && segments.iter().all(|segment| {
+            let mut chars = segment.chars();
+            chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+/// Components are `<package>/<class>`, where the class may be relative to the package (`.Main`).
+fn is_valid_component(component: &str) -> bool {
+    let Some((package, class)) = component.split_once('/') else { return false };
+    let class = class.strip_prefix('.').unwrap_or(class);
+
+    is_valid_package(package)
+        && !class.is_empty()
+        && class.split('.').all(|segment| {
+            let mut chars = segment.chars();
+            chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        })
+}
+
 
 // #####################
 // # ADB MANAGEMENT>   # 
 // #####################
 
-pub fn launch_adb(paths: State<Paths>) {
-    create_silent_command(paths.adb.as_ref().expect("ADB not found"))
+/// Starts the local ADB server on the port of [server]. A remote server is left alone.
+pub fn launch_adb(paths: &Paths, server: &AdbServer) -> Result<(), ZBBError> {
+    if server.is_remote() {
+        return Ok(());
+    }
+
+    let adb = paths.adb.as_ref().ok_or(ZBBError::ADB("ADB nicht gefunden".to_string()))?;
+
+    create_silent_command(adb)
+        .args(server.args())
         .args(vec!["devices".to_string()])
-        .output()
-        .expect("Unable to start ADB");
+        .output()?;
+
+    Ok(())
+}
+
+/// Loads the configured server endpoint, falling back to the local server.
+pub fn load_adb_server(app: &AppHandle) -> AdbServer {
+    load_json(adb_server_path(app).as_deref())
+}
+
+fn adb_server_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path_resolver().app_data_dir().map(|dir| dir.join(ADB_SERVER_FILE))
+}
+
+#[tauri::command]
+pub async fn get_adb_server(client: State<'_, AdbClient>) -> Result<AdbServer, ZBBError> {
+    Ok(client.server())
+}
+
+/// Switches to another ADB server and remembers it for the next start.
+///
+/// A local server is launched if it isn't running yet, a remote one has to be started
+/// on its machine with `adb -a nodaemon server`.
+#[tauri::command]
+pub async fn set_adb_server<'a>(
+    server: AdbServer,
+    app: AppHandle,
+    paths: State<'a, Paths>,
+    client: State<'a, AdbClient>,
+) -> Result<(), ZBBError> {
+    if server.port == 0 {
+        return Err(ZBBError::InvalidArgument("Invalid port: 0".into()));
+    }
+
+    let launch_paths = paths.inner().clone();
+    let launch_server = server.clone();
+    tauri::async_runtime::spawn_blocking(move || launch_adb(&launch_paths, &launch_server))
+        .await
+        .map_err(|err| ZBBError::Other(err.to_string()))??;
+
+    client.set_server(server.clone());
+
+    if let Some(path) = adb_server_path(&app) {
+        save_json(&path, &server)?;
+    }
+
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn kill_server() -> Result<(), ZBBError> {
-    let mut adb = AdbTcpConnection::new(LOOPBACK, ADB_PORT)?;
-    adb.kill()?;
+pub async fn kill_server<'a>(client: State<'a, AdbClient>, operations: State<'a, Operations>) -> Result<(), ZBBError> {
+    operations.deadline(client.run(|adb| adb.kill())).await?;
 
     Ok(())
 }
 
 
 // #####################
-// # MANAGE CONNECTION # 
+// # DEVICE TRACKING   # 
 // #####################
 
-#[tauri::command]
-pub async fn get_devices<'a, 'b>(paths: State<'a, Paths>) -> Result<Vec<LocalDevice>, ZBBError> {
-    let mut adb = AdbTcpConnection::new(LOOPBACK, ADB_PORT).or_else(|error| {
-        if let RustADBError::IOError(io_error) = error {
-            warn!("Unable to connect to adb: {:?}", io_error);
+/// Last device list of the `track-devices` stream, empty while the stream is down.
+#[derive(Default)]
+pub struct DeviceTracker {
+    devices: Mutex<Vec<LocalDevice>>,
+}
+
+impl DeviceTracker {
+    pub fn devices(&self) -> Vec<LocalDevice> {
+        self.devices.lock().unwrap().clone()
+    }
+}
+
+/// Emits the last tracked list again, e.g. after a device has been renamed.
+pub fn emit_devices(app: &AppHandle) -> Result<(), ZBBError> {
+    let devices = app
+        .state::<Registry>()
+        .annotate(app.state::<DeviceTracker>().devices(), &app.state::<Supervisor>());
+
+    app.emit_all(DEVICES_CHANGED_EVENT, devices)
+        .map_err(|err| ZBBError::Other(err.to_string()))
+}
+
+/// Spawns a background thread that follows the `track-devices` stream of the ADB server
+/// and emits [DEVICES_CHANGED_EVENT] whenever a device appears, disappears or changes state.
+///
+/// If the stream breaks (e.g. because the server got killed), a local ADB is relaunched and
+/// tracking resumes. Tracking also restarts when another server is configured.
+pub fn spawn_device_tracker(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut last_devices: Option<Vec<LocalDevice>> = None;
 
-            // Launch ADB
-            launch_adb(paths);
+        loop {
+            if let Err(error) = track_devices(&app, &mut last_devices) {
+                warn!("Device tracking interrupted: {:?}", error);
+            }
+            app.state::<DeviceTracker>().devices.lock().unwrap().clear();
 
-            return AdbTcpConnection::new(LOOPBACK, ADB_PORT);
+            std::thread::sleep(TRACKER_RETRY_DELAY);
+            if let Err(error) = launch_adb(&app.state::<Paths>(), &app.state::<AdbClient>().server()) {
+                warn!("Unable to launch adb: {:?}", error);
+            }
         }
+    });
+}
 
-        Err(error)
-    })?;
+fn track_devices(app: &AppHandle, last_devices: &mut Option<Vec<LocalDevice>>) -> Result<(), ZBBError> {
+    let client = app.state::<AdbClient>();
+    let server = client.server();
 
-    let result = adb
-        .devices()?
-        .into_iter()
-        .map(|it| it.into())
-        .collect::<Vec<_>>();
+    let mut stream = TcpStream::connect((server.host, server.port))?;
+    send_host_request(&mut stream, "host:track-devices")?;
+    stream.set_read_timeout(Some(TRACKER_RETRY_DELAY))?;
 
-    Ok(result)
+    loop {
+        // The stream only carries data when something changes, so check in between
+        // whether the server has been switched
+        match stream.peek(&mut [0u8; 1]) {
+            Ok(0) => return Err(ZBBError::ServerUnavailable),
+            Ok(_) => {}
+            Err(error) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                if client.server() != server {
+                    return Ok(());
+                }
+                continue;
+            }
+            Err(error) => return Err(error.into()),
+        }
+
+        let devices = parse_device_list(&read_host_message(&mut stream)?);
+        *app.state::<DeviceTracker>().devices.lock().unwrap() = devices.clone();
+        let devices = app.state::<Registry>().annotate(devices, &app.state::<Supervisor>());
+
+        if last_devices.as_ref() != Some(&devices) {
+            info!("Devices changed: {:?}", devices);
+            app.emit_all(DEVICES_CHANGED_EVENT, &devices)
+                .map_err(|err| ZBBError::Other(err.to_string()))?;
+            *last_devices = Some(devices);
+        }
+    }
+}
+
+/// Sends a request to the ADB host and checks the `OKAY`/`FAIL` status.
+pub fn send_host_request(stream: &mut TcpStream, request: &str) -> Result<(), ZBBError> {
+    stream.write_all(format!("{:04x}{}", request.len(), request).as_bytes())?;
+
+    let mut status = [0u8; 4];
+    stream.read_exact(&mut status)?;
+
+    match &status {
+        b"OKAY" => Ok(()),
+        _ => Err(ZBBError::ADB(read_host_message(stream)?)),
+    }
+}
+
+/// Reads a single hex-length-prefixed message from the ADB host.
+pub fn read_host_message(stream: &mut TcpStream) -> Result<String, ZBBError> {
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length)?;
+
+    let length = usize::from_str_radix(&String::from_utf8(length.to_vec())?, 16)
+        .map_err(|err| ZBBError::ADB(format!("Invalid message length: {:?}", err)))?;
+
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body)?;
+
+    Ok(String::from_utf8(body)?)
+}
+
+/// Parses the `<serial>\t<state>` lines returned by `host:devices` and `host:track-devices`.
+pub fn parse_device_list(body: &str) -> Vec<LocalDevice> {
+    body.lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(identifier, state)| LocalDevice {
+            identifier: identifier.to_string(),
+            state: LocalDeviceState::from(state.trim()),
+            name: None,
+        })
+        .collect()
+}
+
+/// Parses the lines returned by `host:devices-l`, e.g.
+/// `1WMHH8000000  device usb:1-1 product:hollywood model:Quest_2 device:hollywood transport_id:3`.
+pub fn parse_long_device_list(body: &str) -> Vec<LocalDeviceLong> {
+    body.lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            let identifier = columns.next()?;
+            let state = columns.next()?;
+
+            let values = columns
+                .filter_map(|column| column.split_once(':'))
+                .collect::<HashMap<_, _>>();
+            let value = |key: &str| values.get(key).unwrap_or(&"").to_string();
+
+            Some(LocalDeviceLong {
+                identifier: identifier.to_string(),
+                state: LocalDeviceState::from(state),
+                usb: value("usb"),
+                product: value("product"),
+                model: value("model"),
+                device: value("device"),
+                transport_id: value("transport_id").parse().unwrap_or_default(),
+            })
+        })
+        .collect()
 }
 
 
+// #####################
+// # MANAGE CONNECTION # 
+// #####################
+
 #[tauri::command]
-pub async fn launch_app(id: String, package: String) -> Result<String, ZBBError> {
-    let serial = Some(id);
-    let mut adb = AdbTcpConnection::new(LOOPBACK, ADB_PORT)?;
+pub async fn get_devices<'a>(
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+    registry: State<'a, Registry>,
+    supervisor: State<'a, Supervisor>,
+) -> Result<Vec<LocalDevice>, ZBBError> {
+    let result = operations.deadline(client.run(|adb| adb.devices())).await?;
+
+    Ok(registry.annotate(result, &supervisor))
+}
 
-    // Disable proximity sensor to get the device out of sleep
-    // If we don't do this, the device sometimes gets into a weird state
-    let _ = adb.shell_command(&serial, vec!["am broadcast -a com.oculus.vrpowermanager.prox_close".to_string()]);
 
-    let bytes = if package.contains("/") {
-        adb.shell_command(
-            &serial,
-            vec!["am".into(), "start".into(), "-n".into(), package],
-        )
+/// Lists the devices with the details of `adb devices -l`, e.g. to tell a Quest 2
+/// from a Quest 3 or a USB from a Wi-Fi connection.
+#[tauri::command]
+pub async fn get_devices_long<'a>(client: State<'a, AdbClient>, operations: State<'a, Operations>) -> Result<Vec<LocalDeviceLong>, ZBBError> {
+    operations.deadline(client.run(|adb| adb.devices_long())).await
+}
+
+
+#[tauri::command]
+pub async fn launch_app<'a>(
+    id: String,
+    package: String,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<String, ZBBError> {
+    let serial = Some(id.clone());
+
+    let launch = if package.contains("/") {
+        ShellCommand::new("am").arg("start").arg("-n").component(&package)?
     } else {
-        adb.shell_command(
-            &serial,
-            vec!["monkey".into(), "-p".into(), package, "1".into()],
-        )
-    }?;
+        ShellCommand::new("monkey").arg("-p").package(&package)?.arg("1")
+    }.build();
+
+    let bytes = operations.run(&id, client.run(move |adb| {
+        // Disable proximity sensor to get the device out of sleep
+        // If we don't do this, the device sometimes gets into a weird state
+        let _ = adb.shell_command(&serial, ShellCommand::new("am").arg("broadcast").arg("-a").arg("com.oculus.vrpowermanager.prox_close").build());
+
+        let bytes = adb.shell_command(&serial, launch.clone())?;
 
-    // Enable proximity sensor again
-    let _ = adb.shell_command(&serial, vec!["am broadcast -a com.oculus.vrpowermanager.automation_disable".to_string()]);
+        // Enable proximity sensor again
+        let _ = adb.shell_command(&serial, ShellCommand::new("am").arg("broadcast").arg("-a").arg("com.oculus.vrpowermanager.automation_disable").build());
+
+        Ok(bytes)
+    })).await?;
 
     let result = String::from_utf8(bytes)?;
     Ok(result)
@@ -90,149 +406,806 @@ pub async fn launch_app(id: String, package: String) -> Result<String, ZBBError>
 
 
 #[tauri::command]
-pub async fn connect_device<'a>(id: String, port: u16, paths: State<'a, Paths>) -> Result<String, ZBBError> {
-    let mut adb = AdbTcpConnection::new(LOOPBACK, ADB_PORT)?;
-
-    let serial = Some(id.clone());
+pub async fn connect_device<'a>(
+    id: String,
+    port: u16,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+    supervisor: State<'a, Supervisor>,
+    registry: State<'a, Registry>,
+) -> Result<String, ZBBError> {
+    // Switching to TCP alone may wait for 5 s, too long for the command timeout
+    let ip_address = operations.run_transfer(&id, async {
+        let serial = Some(id.clone());
 
-    let result = adb.shell_command(
-        &serial,
-        vec!["getprop".to_string(), "service.adb.tcp.port".to_string()],
-    )?;
+        let result = client.run(move |adb| adb.shell_command(
+            &serial,
+            ShellCommand::new("getprop").arg("service.adb.tcp.port").build(),
+        )).await?;
 
-    let configured_port = String::from_utf8(result).unwrap();
+        let configured_port = String::from_utf8(result).unwrap();
 
-    info!("Configured port: {}", &configured_port);
-    if configured_port.trim() != port.to_string() {
-        // Switch to TCP
+        info!("Configured port: {}", &configured_port);
+        if configured_port.trim() != port.to_string() {
+            // Switch to TCP
 
-        let adb = paths.adb.as_ref().unwrap();
-        let result = create_silent_command(adb)
-            .args(vec![
+            let result = client.run_adb(vec![
                 "-s".to_string(),
                 id.clone(),
                 "tcpip".to_string(),
                 port.to_string(),
-            ])
-            .output()?;
+            ]).await?;
 
-        info!(
-            "tcpip result: {}",
-            String::from_utf8(result.stdout).unwrap()
-        );
+            info!(
+                "tcpip result: {}",
+                String::from_utf8(result.stdout).unwrap()
+            );
 
-        for _ in 0..5 {
-            async_std::task::sleep(Duration::from_millis(1000)).await;
+            for _ in 0..5 {
+                async_std::task::sleep(Duration::from_millis(1000)).await;
 
-            if get_devices(paths.clone())
-                .await?
-                .iter()
-                .any(|it| &it.identifier == &id)
-            {
-                break;
+                if get_devices(client.clone(), operations.clone(), registry.clone(), supervisor.clone())
+                    .await?
+                    .iter()
+                    .any(|it| &it.identifier == &id)
+                {
+                    break;
+                }
             }
         }
-    }
 
-    let ip_address: Ipv4Addr = get_ip(id.clone())?
-        .parse()
-        .map_err(|err| ZBBError::Other(format!("{:?}", err)))?;
+        let ip_address: Ipv4Addr = read_ip(&client, id.clone())
+            .await?
+            .parse()
+            .map_err(|err| ZBBError::Other(format!("{:?}", err)))?;
 
-    // Check if we're in the same network
-    crate::test_network(ip_address)?;
-
-    if let Err(result) = adb.connect(ip_address, port) {
-        if !result.to_string().contains(" already connected ") {
-            return Err(result.into());
+        // Check if we're in the same network. A remote server connects from its own
+        // machine, so our interfaces don't tell anything then.
+        if !client.server().is_remote() {
+            crate::test_network(ip_address)?;
         }
-    }
+
+        client.run(move |adb| adb.connect(ip_address, port)).await?;
+
+        Ok(ip_address)
+    }).await?;
+
+    supervisor.remember(id, KnownAddress {
+        ip: ip_address.to_string(),
+        port,
+    });
 
     Ok(ip_address.to_string())
 }
 
 #[tauri::command]
-pub async fn connect_to_ip(ip_address: String, port: u16) -> Result<(), ZBBError> {
-    let mut adb = AdbTcpConnection::new(LOOPBACK, ADB_PORT)?;
+pub async fn connect_to_ip<'a>(
+    ip_address: String,
+    port: u16,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<(), ZBBError> {
     let address = Ipv4Addr::from_str(&ip_address)
-        .map_err(|_| ZBBError::Other(format!("Invalid ip address: {}", ip_address)))?;
+        .map_err(|_| ZBBError::InvalidArgument(format!("Invalid ip address: {}", ip_address)))?;
 
-    adb.connect(address, port)?;
+    operations.run(&ip_address, client.run(move |adb| adb.connect(address, port))).await?;
 
     Ok(())
 }
 
 
+/// Pairs with a device that has wireless debugging enabled (Android 11+) and connects to it.
+///
+/// [pairing_port] and [code] are shown in the pairing dialog on the device, [port] is
+/// the port wireless debugging listens on.
 #[tauri::command]
-pub async fn kill_app(id: String, package: String) -> Result<(), ZBBError> {
-    let serial = Some(id);
-    let mut adb = AdbTcpConnection::new(LOOPBACK, ADB_PORT)?;
-    adb.shell_command(&serial, vec!["am".to_string(), "force-stop".to_string(), package])?;
+pub async fn pair_device<'a>(
+    ip_address: String,
+    pairing_port: u16,
+    code: String,
+    port: u16,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<(), ZBBError> {
+    let address = Ipv4Addr::from_str(&ip_address)
+        .map_err(|_| ZBBError::InvalidArgument(format!("Invalid ip address: {}", ip_address)))?;
+
+    if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(ZBBError::InvalidArgument(format!("Invalid pairing code: {}", code)));
+    }
+
+    operations.run(&ip_address, async {
+        let output = client.run_adb(vec![
+            "pair".to_string(),
+            format!("{}:{}", address, pairing_port),
+            code,
+        ]).await?;
+
+        let result = String::from_utf8(output.stdout)?;
+        info!("pair result: {}", result);
+
+        if !result.contains("Successfully paired") {
+            return Err(ZBBError::ADB(result.trim().to_string()));
+        }
+
+        client.run(move |adb| adb.connect(address, port)).await?;
+
+        Ok(())
+    }).await
+}
+
+#[tauri::command]
+pub async fn kill_app<'a>(
+    id: String,
+    package: String,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<(), ZBBError> {
+    let serial = Some(id.clone());
+    let command = ShellCommand::new("am").arg("force-stop").package(&package)?.build();
+
+    operations.run(&id, client.run(move |adb| adb.shell_command(&serial, command.clone()))).await?;
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn shutdown_device(id: String) -> Result<(), ZBBError> {
-    let serial = Some(id);
-    let mut adb = AdbTcpConnection::new(LOOPBACK, ADB_PORT)?;
+pub async fn shutdown_device<'a>(id: String, client: State<'a, AdbClient>, operations: State<'a, Operations>) -> Result<(), ZBBError> {
+    let serial = Some(id.clone());
 
-    adb.shell_command(&serial, vec!["reboot".into(), "-p".into()])?;
+    operations.run(&id, client.run(move |adb| adb.shell_command(&serial, ShellCommand::new("reboot").arg("-p").build()))).await?;
 
     Ok(())
 }
 
+/// Reboots the device or only restarts the Android framework.
+///
+/// With [wait], the command resolves once the device is back in `get_devices`. Devices
+/// connected over Wi-Fi are only back if the supervisor reconnects them.
+#[tauri::command]
+pub async fn reboot_device<'a>(
+    id: String,
+    mode: RebootMode,
+    wait: Option<bool>,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<(), ZBBError> {
+    info!("Rebooting {} ({:?})", id, mode);
+
+    match mode {
+        RebootMode::Normal => {
+            operations.run(&id, client.run_adb(vec!["-s".to_string(), id.clone(), "reboot".to_string()])).await?;
+        }
+        RebootMode::Framework => {
+            let serial = Some(id.clone());
+            operations.run(&id, client.run(move |adb| adb.shell_command(&serial, ShellCommand::new("am").arg("restart").build()))).await?;
+        }
+    }
+
+    if wait.unwrap_or(false) {
+        operations.run_transfer(&id, wait_for_device(&client, &id)).await?;
+    }
+
+    Ok(())
+}
+
+/// Drops the TCP connection of a device, given by its serial or by `ip:port`.
+///
+/// A remembered device is forgotten first, otherwise the supervisor would reconnect it right away.
+#[tauri::command]
+pub async fn disconnect_device<'a>(
+    id: String,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+    supervisor: State<'a, Supervisor>,
+) -> Result<(), ZBBError> {
+    let identifier = match supervisor.find(&id) {
+        Some((serial, address)) => {
+            supervisor.forget(&serial);
+            address.identifier()
+        }
+        None => id.clone(),
+    };
+
+    operations.run(&id, client.run_adb(vec!["disconnect".to_string(), identifier])).await?;
+
+    Ok(())
+}
+
+/// Waits until [id] has left the device list and is back online.
+async fn wait_for_device(client: &AdbClient, id: &str) -> Result<(), ZBBError> {
+    let is_online = move || async move {
+        let devices = client.run(|adb| adb.devices()).await?;
+
+        Ok::<_, ZBBError>(devices
+            .into_iter()
+            .any(|device| device.identifier == id && device.state == LocalDeviceState::Device))
+    };
+
+    // A framework restart keeps the connection, so the device doesn't necessarily go away
+    let grace_period = Instant::now() + REBOOT_GRACE_PERIOD;
+    while Instant::now() < grace_period && is_online().await? {
+        async_std::task::sleep(REBOOT_POLL_INTERVAL).await;
+    }
+
+    while !is_online().await? {
+        async_std::task::sleep(REBOOT_POLL_INTERVAL).await;
+    }
+
+    info!("{} is back online", id);
+    Ok(())
+}
+
+
+// #####################
+// # RECONNECT         # 
+// #####################
+
+/// Remembers the network address of every device connected through [connect_device]
+/// and reconnects devices that drop off the network.
+///
+/// The addresses are persisted, so they survive a restart of the app.
+pub struct Supervisor {
+    path: Option<PathBuf>,
+    addresses: Mutex<HashMap<String, KnownAddress>>,
+}
+
+/// Retry state of a device that is currently not reachable.
+struct Backoff {
+    attempt: u32,
+    delay: Duration,
+    next_attempt: Instant,
+}
+
+impl Supervisor {
+    /// Loads the known addresses from [path], starting empty if there are none yet.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let addresses = load_json(path.as_deref());
+
+        Self {
+            path,
+            addresses: Mutex::new(addresses),
+        }
+    }
+
+    pub fn remember(&self, serial: String, address: KnownAddress) {
+        let mut addresses = self.addresses.lock().unwrap();
+        addresses.insert(serial, address);
+        self.save(&addresses);
+    }
+
+    /// Looks up a remembered device by its serial or by the `ip:port` it is connected on.
+    pub fn find(&self, id: &str) -> Option<(String, KnownAddress)> {
+        self.addresses
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(serial, address)| *serial == id || address.identifier() == id)
+            .map(|(serial, address)| (serial.clone(), address.clone()))
+    }
+
+    fn forget(&self, serial: &str) {
+        let mut addresses = self.addresses.lock().unwrap();
+        addresses.remove(serial);
+        self.save(&addresses);
+    }
+
+    fn save(&self, addresses: &HashMap<String, KnownAddress>) {
+        let Some(path) = &self.path else { return };
+
+        if let Err(error) = save_json(path, addresses) {
+            warn!("Unable to save known addresses: {:?}", error);
+        }
+    }
+}
+
+/// Spawns a background task that reconnects remembered devices which are offline or
+/// missing, with an exponential backoff per device. Every attempt is reported as [RECONNECT_EVENT].
+pub fn spawn_supervisor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut backoffs = HashMap::new();
+
+        loop {
+            async_std::task::sleep(SUPERVISOR_INTERVAL).await;
+
+            if let Err(error) = supervise(&app, &mut backoffs).await {
+                warn!("Unable to supervise devices: {:?}", error);
+            }
+        }
+    });
+}
+
+async fn supervise(app: &AppHandle, backoffs: &mut HashMap<String, Backoff>) -> Result<(), ZBBError> {
+    let client = app.state::<AdbClient>();
+    let operations = app.state::<Operations>();
+
+    let addresses = app.state::<Supervisor>().addresses.lock().unwrap().clone();
+    if addresses.is_empty() {
+        backoffs.clear();
+        return Ok(());
+    }
+
+    // The tracker keeps the list up to date, no need to ask the server again
+    let devices = app.state::<DeviceTracker>().devices();
+
+    for (serial, address) in addresses {
+        let identifier = address.identifier();
+        let state = devices
+            .iter()
+            .find(|device| device.identifier == identifier)
+            .map(|device| device.state.clone());
+
+        if state == Some(LocalDeviceState::Device) {
+            backoffs.remove(&serial);
+            continue;
+        }
+
+        let backoff = backoffs.entry(serial.clone()).or_insert(Backoff {
+            attempt: 0,
+            delay: MIN_RECONNECT_DELAY,
+            next_attempt: Instant::now(),
+        });
+        if Instant::now() < backoff.next_attempt {
+            continue;
+        }
+        backoff.attempt += 1;
+
+        // An offline transport has to be dropped, otherwise adb claims to be already connected
+        if state == Some(LocalDeviceState::Offline) {
+            let _ = operations.deadline(client.run_adb(vec!["disconnect".to_string(), identifier.clone()])).await;
+        }
+
+        let port = address.port;
+        let result = match Ipv4Addr::from_str(&address.ip) {
+            Ok(ip) => operations.deadline(client.run(move |adb| adb.connect(ip, port))).await,
+            Err(error) => Err(error.into()),
+        };
+        let connected = result.is_ok();
+        info!("Reconnecting {} to {} (attempt {}): {:?}", serial, identifier, backoff.attempt, result);
+
+        let _ = app.emit_all(RECONNECT_EVENT, ReconnectAttempt {
+            serial: serial.clone(),
+            address: identifier,
+            attempt: backoff.attempt,
+            connected,
+        });
+
+        if connected {
+            backoffs.remove(&serial);
+        } else {
+            backoff.next_attempt = Instant::now() + backoff.delay;
+            backoff.delay = (backoff.delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    }
+
+    Ok(())
+}
+
+/// Lists the remembered network addresses by serial.
+#[tauri::command]
+pub async fn get_known_addresses(supervisor: State<'_, Supervisor>) -> Result<HashMap<String, KnownAddress>, ZBBError> {
+    Ok(supervisor.addresses.lock().unwrap().clone())
+}
+
+/// Stops reconnecting the device and forgets its address.
+#[tauri::command]
+pub async fn forget_device(id: String, supervisor: State<'_, Supervisor>) -> Result<(), ZBBError> {
+    supervisor.forget(&id);
+
+    Ok(())
+}
+
+
+// #####################
+// # MANAGE PACKAGES   # 
+// #####################
+
+/// Installs the APK at [apk_path] on the host onto the device.
+///
+/// Existing installations are always upgraded in place. With [downgrade], an older
+/// version code may replace a newer one. Every step is reported as [INSTALL_PROGRESS_EVENT].
+#[tauri::command]
+pub async fn install_apk<'a>(
+    id: String,
+    apk_path: String,
+    downgrade: bool,
+    app: AppHandle,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<(), ZBBError> {
+    let result = operations
+        .run_transfer(&id, install(&id, apk_path, downgrade, &app, &client))
+        .await;
+
+    emit_install_progress(&app, &id, match &result {
+        Ok(_) => InstallStage::Finished,
+        Err(error) => InstallStage::Failed(format!("{:?}", error)),
+    });
+
+    result
+}
+
+async fn install(
+    id: &str,
+    apk_path: String,
+    downgrade: bool,
+    app: &AppHandle,
+    client: &AdbClient,
+) -> Result<(), ZBBError> {
+    let serial = Some(id.to_string());
+
+    emit_install_progress(app, id, InstallStage::Uploading);
+    client.run_adb(vec![
+        "-s".to_string(),
+        id.to_string(),
+        "push".to_string(),
+        apk_path,
+        REMOTE_APK_PATH.to_string(),
+    ]).await?;
+
+    emit_install_progress(app, id, InstallStage::Installing);
+    let mut install = ShellCommand::new("pm").arg("install").arg("-r");
+    if downgrade {
+        install = install.arg("-d");
+    }
+    let install = install.arg(REMOTE_APK_PATH).build();
+
+    let install_serial = serial.clone();
+    // pm stays silent until the install is done
+    let result = client.run_transfer(move |adb| adb.shell_command(&install_serial, install.clone())).await;
+
+    // Don't leave the APK lying around, whether the install worked or not
+    let _ = client.run(move |adb| adb.shell_command(&serial, ShellCommand::new("rm").arg("-f").arg(REMOTE_APK_PATH).build())).await;
+
+    parse_package_manager_result(&String::from_utf8(result?)?)
+}
+
+/// Removes [package] from the device. With [keep_data], the app data and caches are kept.
+#[tauri::command]
+pub async fn uninstall_app<'a>(
+    id: String,
+    package: String,
+    keep_data: bool,
+    app: AppHandle,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<(), ZBBError> {
+    let serial = Some(id.clone());
+
+    let mut uninstall = ShellCommand::new("pm").arg("uninstall");
+    if keep_data {
+        uninstall = uninstall.arg("-k");
+    }
+    let uninstall = uninstall.package(&package)?.build();
+
+    emit_install_progress(&app, &id, InstallStage::Uninstalling);
+    let result = match operations.run(&id, client.run(move |adb| adb.shell_command(&serial, uninstall.clone()))).await {
+        Ok(bytes) => parse_package_manager_result(&String::from_utf8(bytes)?),
+        Err(error) => Err(error),
+    };
+
+    emit_install_progress(&app, &id, match &result {
+        Ok(_) => InstallStage::Finished,
+        Err(error) => InstallStage::Failed(format!("{:?}", error)),
+    });
+
+    result
+}
+
+/// Lists the third-party packages on the device with their version, optionally only
+/// those whose name contains [filter].
+#[tauri::command]
+pub async fn list_packages<'a>(
+    id: String,
+    filter: Option<String>,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<Vec<PackageInfo>, ZBBError> {
+    let serial = Some(id.clone());
+
+    let mut list = ShellCommand::new("pm").arg("list").arg("packages").arg("-3");
+    if let Some(filter) = filter {
+        list = list.arg(filter);
+    }
+    let list = list.build();
+
+    operations.run(&id, client.run(move |adb| {
+        let output = adb.shell_command(&serial, list.clone())?;
+        // A single dump for all packages, one dumpsys per package does not fit into the command timeout
+        let dump = adb.shell_command(&serial, ShellCommand::new("dumpsys").arg("package").arg("packages").build())?;
+        let dump = String::from_utf8_lossy(&dump);
+
+        Ok(parse_package_list(&String::from_utf8_lossy(&output))
+            .into_iter()
+            .map(|name| {
+                let section = package_section(&dump, &name);
+                parse_package_info(name, section)
+            })
+            .collect())
+    })).await
+}
+
+fn emit_install_progress(app: &AppHandle, serial: &str, stage: InstallStage) {
+    let progress = InstallProgress {
+        serial: serial.to_string(),
+        stage,
+    };
+
+    if let Err(error) = app.emit_all(INSTALL_PROGRESS_EVENT, progress) {
+        warn!("Unable to emit install progress: {:?}", error);
+    }
+}
+
+/// Parses the `package:<name>` lines of `pm list packages`.
+fn parse_package_list(output: &str) -> Vec<String> {
+    let mut packages = output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("package:"))
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    packages.sort();
+
+    packages
+}
+
+/// Cuts the section of [name] out of `dumpsys package packages`, up to the header of the next
+/// package. Empty if the package is not listed.
+fn package_section<'a>(dump: &'a str, name: &str) -> &'a str {
+    let header = format!("Package [{}]", name);
+    let Some(start) = dump.find(&header) else { return "" };
+    let section = &dump[start..];
+    let end = section[header.len()..]
+        .find("Package [")
+        .map_or(section.len(), |end| end + header.len());
+
+    &section[..end]
+}
+
+/// Reads the version of [name] from its `dumpsys package` section. If several versions are
+/// listed (e.g. an updated system app), the first one wins.
+fn parse_package_info(name: String, dump: &str) -> PackageInfo {
+    let value = |key: &str| {
+        dump.split_whitespace()
+            .find_map(|entry| entry.strip_prefix(key))
+            .map(|value| value.to_string())
+    };
+    let time = |key: &str| {
+        dump.lines()
+            .find_map(|line| line.trim().strip_prefix(key))
+            .map(|value| value.to_string())
+    };
+
+    PackageInfo {
+        name,
+        version_name: value("versionName="),
+        version_code: value("versionCode=").and_then(|code| code.parse().ok()),
+        first_install_time: time("firstInstallTime="),
+        last_update_time: time("lastUpdateTime="),
+    }
+}
+
+/// Interprets the output of `pm install`/`pm uninstall`, which ends with either
+/// `Success` or `Failure [<REASON>]`.
+fn parse_package_manager_result(output: &str) -> Result<(), ZBBError> {
+    let last_line = output.trim().lines().last().unwrap_or("").trim();
+
+    if last_line == "Success" {
+        return Ok(());
+    }
+
+    let reason = last_line
+        .strip_prefix("Failure [")
+        .and_then(|reason| reason.strip_suffix(']'))
+        .unwrap_or(last_line);
+
+    Err(ZBBError::PackageManager(reason.to_string()))
+}
 
 // #####################
 // # GET INFORMATION   # 
 // #####################
 
 #[tauri::command]
-pub async fn is_running(id: String, package: String) -> Result<bool, ZBBError> {
-    let serial = Some(id);
-    let mut adb = AdbTcpConnection::new(LOOPBACK, ADB_PORT)?;
+pub async fn is_running<'a>(
+    id: String,
+    package: String,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<bool, ZBBError> {
+    let serial = Some(id.clone());
+
+    let command = ShellCommand::new("pidof").package(&package)?.build();
 
-    let result = adb.shell_command(&serial, vec!["pidof".into(), package])?;
+    let result = operations.run(&id, client.run(move |adb| adb.shell_command(&serial, command.clone()))).await?;
 
     Ok(!result.is_empty())
 }
 
 
+/// Tells whether [package] is what the participant currently sees, only cached in the
+/// background, or not running at all.
 #[tauri::command]
-pub async fn is_screen_on(id: String) -> Result<bool, ZBBError> {
-    let serial = Some(id);
-    let mut adb = AdbTcpConnection::new(LOOPBACK, ADB_PORT)?;
+pub async fn get_app_state<'a>(
+    id: String,
+    package: String,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<AppActivity, ZBBError> {
+    let serial = Some(id.clone());
+    let package = package.split('/').next().unwrap_or_default().to_string();
+
+    let pidof = ShellCommand::new("pidof").package(&package)?.build();
+
+    let (activities, pid) = operations.run(&id, client.run(move |adb| {
+        let activities = adb.shell_command(&serial, ShellCommand::new("dumpsys").arg("activity").arg("activities").build())?;
+        let pid = adb.shell_command(&serial, pidof.clone())?;
+
+        Ok((activities, pid))
+    })).await?;
+
+    let resumed_activity = parse_resumed_activity(&String::from_utf8(activities)?);
+    let foreground_package = resumed_activity
+        .as_ref()
+        .and_then(|activity| activity.split('/').next())
+        .map(|package| package.to_string());
 
-    let result = adb.shell_command(&serial, vec!["dumpsys deviceidle | grep mScreenOn".into()])?;
+    let state = if foreground_package.as_ref() == Some(&package) {
+        AppState::Foreground
+    } else if !pid.is_empty() {
+        AppState::Background
+    } else {
+        AppState::Stopped
+    };
+
+    Ok(AppActivity {
+        state,
+        resumed_activity,
+        foreground_package,
+    })
+}
+
+/// Finds the resumed activity (`<package>/<activity>`) in `dumpsys activity activities`,
+/// which lists it as `mResumedActivity: ActivityRecord{<hash> u0 <package>/<activity> t<task>}`
+/// or `topResumedActivity=ActivityRecord{...}` on newer Android versions.
+fn parse_resumed_activity(dump: &str) -> Option<String> {
+    dump.lines()
+        .map(|line| line.trim())
+        .filter(|line| line.starts_with("mResumedActivity") || line.starts_with("topResumedActivity"))
+        .find_map(|line| {
+            line.split_whitespace()
+                .find(|word| word.contains('/'))
+                .map(|activity| activity.trim_end_matches('}').to_string())
+        })
+}
+
+#[tauri::command]
+pub async fn is_screen_on<'a>(id: String, client: State<'a, AdbClient>, operations: State<'a, Operations>) -> Result<bool, ZBBError> {
+    let serial = Some(id.clone());
+
+    let result = operations
+        .run(&id, client.run(move |adb| adb.shell_command(&serial, ShellCommand::new("dumpsys").arg("deviceidle").build())))
+        .await?;
     let result_string = String::from_utf8(result).map_err(|err| ZBBError::Other(err.to_string()))?;
 
-    Ok(result_string.split_once('=').map(|it| it.1.trim() == "true").unwrap_or(false))
+    Ok(result_string
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("mScreenOn="))
+        .map(|it| it.trim() == "true")
+        .unwrap_or(false))
 }
 
 
 #[tauri::command]
-pub async fn get_battery_level(id: String) -> Result<i32, ZBBError> {
+pub async fn get_battery_level<'a>(
+    id: String,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<BatteryStatus, ZBBError> {
+    let serial = Some(id.clone());
+
+    let battery_bytes = operations
+        .run(&id, client.run(move |adb| adb.shell_command(&serial, ShellCommand::new("dumpsys").arg("battery").build())))
+        .await?;
+    let mut status = parse_battery_status(&String::from_utf8(battery_bytes)?)?;
+
+    status.controllers = operations.run(&id, read_controllers(&client, id.clone())).await.unwrap_or_else(|error| {
+        warn!("Unable to read controllers: {:?}", error);
+        vec![]
+    });
+
+    Ok(status)
+}
+
+/// Gets battery level and connection state of the controllers paired with the headset.
+#[tauri::command]
+pub async fn get_controller_status<'a>(
+    id: String,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<Vec<ControllerStatus>, ZBBError> {
+    operations.run(&id, read_controllers(&client, id.clone())).await
+}
+
+async fn read_controllers(client: &AdbClient, id: String) -> Result<Vec<ControllerStatus>, ZBBError> {
     let serial = Some(id);
-    let mut adb = AdbTcpConnection::new(LOOPBACK, ADB_PORT)?;
 
-    let level_bytes = adb.shell_command(&serial, vec!["dumpsys battery | grep level".into()])?;
-    let level_string = String::from_utf8(level_bytes).map_err(|_| ZBBError::Other("Konnte Batteriestand nicht holen.".into()))?;
+    let remote_bytes = client
+        .run(move |adb| adb.shell_command(&serial, ShellCommand::new("dumpsys").arg("OVRRemoteService").build()))
+        .await?;
 
-    let level = level_string.split(':').into_iter().nth(1).and_then(|number| number.trim().parse::<i32>().ok());
+    Ok(parse_controllers(&String::from_utf8(remote_bytes)?))
+}
 
-    match level {
-        Some(battery) => Ok(battery),
-        None => Err(ZBBError::Other("Unbekannter Batteriestand".into()))
-    }
+/// Parses the `key: value` lines of `dumpsys battery`.
+fn parse_battery_status(dump: &str) -> Result<BatteryStatus, ZBBError> {
+    let values = dump
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect::<HashMap<_, _>>();
+
+    let number = |key: &str| values.get(key).and_then(|value| value.parse::<i32>().ok());
+    let is_powered = |key: &str| values.get(key) == Some(&"true");
+
+    let power_source = if is_powered("AC powered") {
+        Some(PowerSource::AC)
+    } else if is_powered("USB powered") {
+        Some(PowerSource::USB)
+    } else if is_powered("Wireless powered") {
+        Some(PowerSource::Wireless)
+    } else {
+        None
+    };
+
+    Ok(BatteryStatus {
+        level: number("level").ok_or(ZBBError::Other("Unknown battery level".into()))?,
+        charging: number("status").unwrap_or_default().into(),
+        power_source,
+        temperature: number("temperature").unwrap_or_default() as f32 / 10.0,
+        health: number("health").unwrap_or_default().into(),
+        voltage: number("voltage").unwrap_or_default(),
+        controllers: vec![],
+    })
+}
+
+/// Parses the paired devices of `dumpsys OVRRemoteService`, which are listed as
+/// `Paired device: <id>, Type: Right, ..., Battery: 80%, Status: Connected, ...`.
+fn parse_controllers(dump: &str) -> Vec<ControllerStatus> {
+    dump.lines()
+        .filter(|line| line.contains("Battery:"))
+        .map(|line| {
+            let values = line
+                .split(',')
+                .filter_map(|entry| entry.split_once(':'))
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .collect::<HashMap<_, _>>();
+
+            ControllerStatus {
+                hand: match values.get("Type") {
+                    Some(&"Left") => Hand::Left,
+                    Some(&"Right") => Hand::Right,
+                    _ => Hand::Unknown,
+                },
+                level: values
+                    .get("Battery")
+                    .and_then(|level| level.trim_end_matches('%').parse::<i32>().ok()),
+                connected: values.get("Status") == Some(&"Connected"),
+            }
+        })
+        .collect()
 }
 
 
 /// Gets the IP address of an Android device
 #[tauri::command]
-pub fn get_ip(id: String) -> Result<String, ZBBError> {
-    let mut adb = AdbTcpConnection::new(LOOPBACK, ADB_PORT)?;
+pub async fn get_ip<'a>(id: String, client: State<'a, AdbClient>, operations: State<'a, Operations>) -> Result<String, ZBBError> {
+    operations.run(&id, read_ip(&client, id.clone())).await
+}
+
+async fn read_ip(client: &AdbClient, id: String) -> Result<String, ZBBError> {
     let serial = Some(id);
 
-    let ip_route = adb.shell_command(&serial, vec!["ip".to_string(), "route".to_string()])?;
+    let ip_route = client.run(move |adb| adb.shell_command(&serial, ShellCommand::new("ip").arg("route").build())).await?;
     let ip_address: String = String::from_utf8(ip_route)
         .expect("Failed to parse `ip route` result")
         .lines()
@@ -245,3 +1218,262 @@ pub fn get_ip(id: String) -> Result<String, ZBBError> {
 
     Ok(ip_address)
 }
+
+/// Gets the Wi-Fi link of the device, e.g. to see why mirroring stutters.
+#[tauri::command]
+pub async fn get_wifi_status<'a>(id: String, client: State<'a, AdbClient>, operations: State<'a, Operations>) -> Result<WifiStatus, ZBBError> {
+    let serial = Some(id.clone());
+
+    let dump = operations
+        .run(&id, client.run(move |adb| adb.shell_command(&serial, ShellCommand::new("dumpsys").arg("wifi").build())))
+        .await?;
+
+    parse_wifi_status(&String::from_utf8(dump)?)
+}
+
+/// Parses the `mWifiInfo` line of `dumpsys wifi`, which looks like
+/// `mWifiInfo SSID: "ZBB", BSSID: aa:bb:cc:dd:ee:ff, ..., RSSI: -52, Link speed: 433Mbps, ..., Frequency: 5180MHz, ...`.
+///
+/// SSIDs containing `, ` aren't supported.
+fn parse_wifi_status(dump: &str) -> Result<WifiStatus, ZBBError> {
+    let line = dump
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("mWifiInfo "))
+        .ok_or(ZBBError::NotInANetwork)?;
+
+    let values = line
+        .split(", ")
+        .filter_map(|entry| entry.split_once(": "))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect::<HashMap<_, _>>();
+
+    if values.get("Supplicant state") != Some(&"COMPLETED") {
+        return Err(ZBBError::NotInANetwork);
+    }
+
+    let number = |key: &str, unit: &str| {
+        values
+            .get(key)
+            .and_then(|value| value.trim_end_matches(unit).parse::<i32>().ok())
+    };
+    let frequency = number("Frequency", "MHz").unwrap_or_default();
+
+    Ok(WifiStatus {
+        ssid: values.get("SSID").unwrap_or(&"").trim_matches('"').to_string(),
+        bssid: values.get("BSSID").unwrap_or(&"").to_string(),
+        rssi: number("RSSI", "").unwrap_or_default(),
+        link_speed: number("Link speed", "Mbps"),
+        frequency,
+        band: frequency.into(),
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{BatteryHealth, ChargingState, WifiBand};
+
+    #[test]
+    fn test_quote() {
+        assert_eq!("ch.sbb.xr.zbbvr/.MainActivity", quote("ch.sbb.xr.zbbvr/.MainActivity"));
+        assert_eq!("'a b'", quote("a b"));
+        assert_eq!("'; reboot'", quote("; reboot"));
+        assert_eq!("'it'\\''s'", quote("it's"));
+        assert_eq!("''", quote(""));
+    }
+
+    #[test]
+    fn test_validate_names() {
+        assert!(is_valid_package("ch.sbb.xr.zbbvr"));
+        assert!(!is_valid_package("zbbvr"));
+        assert!(!is_valid_package("ch.sbb.xr.zbbvr; reboot"));
+        assert!(!is_valid_package("ch.1sbb"));
+
+        assert!(is_valid_component("ch.sbb.xr.zbbvr/.MainActivity"));
+        assert!(is_valid_component("ch.sbb.xr.zbbvr/com.unity3d.player.UnityPlayerActivity"));
+        assert!(!is_valid_component("ch.sbb.xr.zbbvr"));
+        assert!(!is_valid_component("ch.sbb.xr.zbbvr/.Main && reboot"));
+    }
+
+    #[test]
+    fn test_shell_command() {
+        assert!(ShellCommand::new("pidof").package("$(reboot)").is_err());
+        assert_eq!(
+            vec!["pm", "list", "packages", "-3", "'zbb vr'"],
+            ShellCommand::new("pm").arg("list").arg("packages").arg("-3").arg("zbb vr").build()
+        );
+    }
+
+    #[test]
+    fn test_parse_device_list() {
+        let devices = parse_device_list("1WMHH8000000\tdevice\n192.168.1.20:5555\toffline\n2G0YC1ZF\tunauthorized\n");
+
+        assert_eq!(3, devices.len());
+        assert_eq!("1WMHH8000000", devices[0].identifier);
+        assert_eq!(LocalDeviceState::Device, devices[0].state);
+        assert_eq!(LocalDeviceState::Offline, devices[1].state);
+        assert_eq!(LocalDeviceState::Unauthorized, devices[2].state);
+    }
+
+    #[test]
+    fn test_parse_empty_device_list() {
+        assert!(parse_device_list("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_long_device_list() {
+        let devices = parse_long_device_list("1WMHH8000000           device usb:1-1 product:hollywood model:Quest_2 device:hollywood transport_id:3
+192.168.1.20:5555      offline product:eureka model:Quest_3 device:eureka transport_id:5
+");
+
+        assert_eq!(2, devices.len());
+        assert_eq!("Quest_2", devices[0].model);
+        assert_eq!("1-1", devices[0].usb);
+        assert_eq!(3, devices[0].transport_id);
+        assert_eq!(LocalDeviceState::Offline, devices[1].state);
+        assert_eq!("", devices[1].usb);
+    }
+
+    #[test]
+    fn test_find_known_address() {
+        let supervisor = Supervisor::load(None);
+        let address = KnownAddress {
+            ip: "192.168.1.20".to_string(),
+            port: 5555,
+        };
+        supervisor.remember("1WMHH812345678".to_string(), address.clone());
+
+        let expected = Some(("1WMHH812345678".to_string(), address));
+        assert_eq!(expected, supervisor.find("1WMHH812345678"));
+        assert_eq!(expected, supervisor.find("192.168.1.20:5555"));
+        assert_eq!(None, supervisor.find("192.168.1.21:5555"));
+    }
+
+    #[test]
+    fn test_parse_battery_status() {
+        let dump = "Current Battery Service state:
+  AC powered: false
+  USB powered: true
+  Wireless powered: false
+  status: 2
+  health: 2
+  present: true
+  level: 85
+  scale: 100
+  voltage: 4123
+  temperature: 291
+  technology: Li-ion
+";
+        let status = parse_battery_status(dump).unwrap();
+
+        assert_eq!(85, status.level);
+        assert_eq!(ChargingState::Charging, status.charging);
+        assert_eq!(Some(PowerSource::USB), status.power_source);
+        assert_eq!(BatteryHealth::Good, status.health);
+        assert_eq!(4123, status.voltage);
+        assert!((status.temperature - 29.1).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_controllers() {
+        let dump = "Remote Service:
+  Paired device: 7d6a1b2c, Type: Left, Firmware: 1.2.3, Battery: 60%, Status: Connected
+  Paired device: 8e7b2c3d, Type: Right, Firmware: 1.2.3, Battery: 5%, Status: Disconnected
+";
+        let controllers = parse_controllers(dump);
+
+        assert_eq!(2, controllers.len());
+        assert_eq!(Hand::Left, controllers[0].hand);
+        assert_eq!(Some(60), controllers[0].level);
+        assert!(controllers[0].connected);
+        assert_eq!(Hand::Right, controllers[1].hand);
+        assert_eq!(Some(5), controllers[1].level);
+        assert!(!controllers[1].connected);
+    }
+
+    #[test]
+    fn test_parse_battery_status_without_level() {
+        assert!(parse_battery_status("Current Battery Service state:\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_wifi_status() {
+        let dump = "Wi-Fi is enabled
+mWifiInfo SSID: \"ZBB-Training\", BSSID: 3c:28:6d:12:34:56, MAC: 02:00:00:00:00:00, Supplicant state: COMPLETED, Wi-Fi standard: 11ac, RSSI: -61, Link speed: 390Mbps, Tx Link speed: 390Mbps, Rx Link speed: 351Mbps, Frequency: 5220MHz, Net ID: 0, Metered hint: false
+";
+        let status = parse_wifi_status(dump).unwrap();
+
+        assert_eq!("ZBB-Training", status.ssid);
+        assert_eq!("3c:28:6d:12:34:56", status.bssid);
+        assert_eq!(-61, status.rssi);
+        assert_eq!(Some(390), status.link_speed);
+        assert_eq!(WifiBand::Band5, status.band);
+
+        let disconnected = "mWifiInfo SSID: <unknown ssid>, BSSID: <none>, Supplicant state: DISCONNECTED, RSSI: -127";
+        assert!(matches!(parse_wifi_status(disconnected), Err(ZBBError::NotInANetwork)));
+    }
+
+    #[test]
+    fn test_parse_resumed_activity() {
+        let dump = "ACTIVITY MANAGER ACTIVITIES (dumpsys activity activities)
+Display #0 (activities from top to bottom):
+  * Task{8a3c1e2 #12 type=standard A=10123:ch.sbb.xr.zbbvr U=0 visible=true}
+    mResumedActivity: ActivityRecord{5f1d0e u0 ch.sbb.xr.zbbvr/com.unity3d.player.UnityPlayerActivity t12}
+";
+
+        assert_eq!(
+            Some("ch.sbb.xr.zbbvr/com.unity3d.player.UnityPlayerActivity".to_string()),
+            parse_resumed_activity(dump)
+        );
+        assert_eq!(
+            Some("com.oculus.vrshell/.MainActivity".to_string()),
+            parse_resumed_activity("  topResumedActivity=ActivityRecord{9b2 u0 com.oculus.vrshell/.MainActivity t3}")
+        );
+        assert_eq!(None, parse_resumed_activity("  mResumedActivity: null"));
+    }
+
+    #[test]
+    fn test_parse_packages() {
+        let packages = parse_package_list("package:com.oculus.browser\npackage:ch.sbb.xr.zbbvr\n");
+        assert_eq!(vec!["ch.sbb.xr.zbbvr", "com.oculus.browser"], packages);
+
+        let dump = "Packages:
+  Package [ch.sbb.xr.zbbvr] (4b1c2d3):
+    userId=10123
+    versionCode=42 minSdk=29 targetSdk=32
+    versionName=1.4.2
+    timeStamp=2024-05-02 09:13:55
+    firstInstallTime=2024-04-01 08:00:12
+    lastUpdateTime=2024-05-02 09:14:01
+  Package [com.oculus.browser] (8e7f6a5):
+    userId=10087
+    versionCode=7 minSdk=29 targetSdk=32
+    versionName=30.1.0
+    firstInstallTime=2023-11-20 10:02:44
+    lastUpdateTime=2024-03-15 16:40:09
+";
+        let info = parse_package_info("ch.sbb.xr.zbbvr".to_string(), package_section(dump, "ch.sbb.xr.zbbvr"));
+
+        assert_eq!(Some("1.4.2".to_string()), info.version_name);
+        assert_eq!(Some(42), info.version_code);
+        assert_eq!(Some("2024-04-01 08:00:12".to_string()), info.first_install_time);
+        assert_eq!(Some("2024-05-02 09:14:01".to_string()), info.last_update_time);
+
+        let info = parse_package_info("com.oculus.browser".to_string(), package_section(dump, "com.oculus.browser"));
+        assert_eq!(Some(7), info.version_code);
+
+        let info = parse_package_info("com.unknown".to_string(), package_section(dump, "com.unknown"));
+        assert_eq!(None, info.version_code);
+    }
+
+    #[test]
+    fn test_parse_package_manager_result() {
+        assert!(parse_package_manager_result("Performing Streamed Install\nSuccess\n").is_ok());
+
+        match parse_package_manager_result("Failure [INSTALL_FAILED_VERSION_DOWNGRADE: Downgrade detected]\n") {
+            Err(ZBBError::PackageManager(reason)) => assert_eq!("INSTALL_FAILED_VERSION_DOWNGRADE: Downgrade detected", reason),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Semaphore;
+use crate::adb::{kill_app, launch_app, shutdown_device};
+use crate::structs::ZBBError;
+
+/// Number of devices a batch command talks to at the same time, unless specified otherwise.
+const DEFAULT_PARALLELISM: usize = 4;
+
+pub type BatchResult<T> = HashMap<String, Result<T, ZBBError>>;
+
+#[tauri::command]
+pub async fn launch_app_batch(
+    ids: Vec<String>,
+    package: String,
+    parallelism: Option<usize>,
+    app: AppHandle,
+) -> Result<BatchResult<String>, ZBBError> {
+    Ok(run_batch(ids, parallelism, app, move |app, id| {
+        let package = package.clone();
+        async move { launch_app(id, package, app.state(), app.state()).await }
+    }).await)
+}
+
+#[tauri::command]
+pub async fn kill_app_batch(
+    ids: Vec<String>,
+    package: String,
+    parallelism: Option<usize>,
+    app: AppHandle,
+) -> Result<BatchResult<()>, ZBBError> {
+    Ok(run_batch(ids, parallelism, app, move |app, id| {
+        let package = package.clone();
+        async move { kill_app(id, package, app.state(), app.state()).await }
+    }).await)
+}
+
+#[tauri::command]
+pub async fn shutdown_device_batch(
+    ids: Vec<String>,
+    parallelism: Option<usize>,
+    app: AppHandle,
+) -> Result<BatchResult<()>, ZBBError> {
+    Ok(run_batch(ids, parallelism, app, |app, id| async move {
+        shutdown_device(id, app.state(), app.state()).await
+    }).await)
+}
+
+/// Runs [command] for every serial in [ids], with at most [parallelism] running at once.
+///
+/// Every device gets its own result, so a single unreachable headset neither stalls
+/// nor hides the outcome for the others.
+pub async fn run_batch<T, F, Fut>(
+    ids: Vec<String>,
+    parallelism: Option<usize>,
+    app: AppHandle,
+    command: F,
+) -> BatchResult<T>
+where
+    T: Send + 'static,
+    F: Fn(AppHandle, String) -> Fut,
+    Fut: Future<Output = Result<T, ZBBError>> + Send + 'static,
+{
+    let permits = Arc::new(Semaphore::new(parallelism.unwrap_or(DEFAULT_PARALLELISM).max(1)));
+
+    let tasks = ids
+        .into_iter()
+        .map(|id| {
+            let permits = permits.clone();
+            let future = command(app.clone(), id.clone());
+
+            let task = tauri::async_runtime::spawn(async move {
+                let _permit = permits
+                    .acquire_owned()
+                    .await
+                    .map_err(|err| ZBBError::Other(err.to_string()))?;
+
+                future.await
+            });
+
+            (id, task)
+        })
+        .collect::<Vec<_>>();
+
+    let mut results = HashMap::new();
+    for (id, task) in tasks {
+        let result = task
+            .await
+            .unwrap_or_else(|err| Err(ZBBError::Other(err.to_string())));
+
+        results.insert(id, result);
+    }
+
+    results
+}
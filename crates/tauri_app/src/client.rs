@@ -0,0 +1,234 @@
+use std::io::{ErrorKind, Read};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use log::{info, warn};
+use tokio::sync::Semaphore;
+use crate::adb::{launch_adb, parse_device_list, parse_long_device_list, read_host_message, send_host_request};
+use crate::structs::{AdbServer, LocalDevice, LocalDeviceLong, Paths, Timeouts, ZBBError};
+use crate::util::create_silent_command;
+
+/// Upper bound of connections to the ADB server that may be in use at the same time.
+pub const MAX_ADB_CONNECTIONS: usize = 8;
+
+/// Upper bound of long transfers like `pm install`, counted apart from [MAX_ADB_CONNECTIONS]
+/// so they can't hold up short commands for minutes.
+pub const MAX_ADB_TRANSFERS: usize = 4;
+
+/// Runs commands against the ADB server, with a limited number of sockets open at the
+/// same time.
+///
+/// It also owns the [AdbServer] endpoint, so both the ADB protocol and the adb
+/// binary ([AdbClient::command], [AdbClient::run_adb]) talk to the same server.
+pub struct AdbClient {
+    paths: Paths,
+    server: RwLock<AdbServer>,
+    timeouts: Arc<Mutex<Timeouts>>,
+    permits: Arc<Semaphore>,
+    transfer_permits: Arc<Semaphore>,
+}
+
+impl AdbClient {
+    pub fn new(
+        paths: Paths,
+        server: AdbServer,
+        timeouts: Arc<Mutex<Timeouts>>,
+        max_connections: usize,
+        max_transfers: usize,
+    ) -> Self {
+        Self {
+            paths,
+            server: RwLock::new(server),
+            timeouts,
+            permits: Arc::new(Semaphore::new(max_connections)),
+            transfer_permits: Arc::new(Semaphore::new(max_transfers)),
+        }
+    }
+
+    pub fn server(&self) -> AdbServer {
+        self.server.read().unwrap().clone()
+    }
+
+    /// Switches to another server. Commands that are already running finish on the previous one.
+    pub fn set_server(&self, server: AdbServer) {
+        info!("Using adb server {:?}", server);
+        *self.server.write().unwrap() = server;
+    }
+
+    /// Creates an adb process that talks to the configured server.
+    pub fn command(&self) -> Result<Command, ZBBError> {
+        let adb = self.paths.adb.as_ref().ok_or(ZBBError::ADB("ADB nicht gefunden".to_string()))?;
+
+        let mut command = create_silent_command(adb);
+        command.args(self.server().args());
+
+        Ok(command)
+    }
+
+    /// Runs the adb binary with [args] on a blocking thread. Fails with the output of
+    /// stderr if adb exits unsuccessfully.
+    pub async fn run_adb(&self, args: Vec<String>) -> Result<Output, ZBBError> {
+        let mut command = self.command()?;
+
+        let output = tauri::async_runtime::spawn_blocking(move || command.args(args).output())
+            .await
+            .map_err(|err| ZBBError::Other(err.to_string()))??;
+
+        if !output.status.success() {
+            return Err(ZBBError::ADB(String::from_utf8(output.stderr)?.trim().to_string()));
+        }
+
+        Ok(output)
+    }
+
+    /// Runs [command] on a blocking thread with a connection to the server.
+    ///
+    /// Reads and writes of the connection fail once the command timeout is up, so a
+    /// command stuck on a half-dead device ends even if nobody waits for it anymore.
+    /// If the server can't be reached, a local one is relaunched and [command] is retried once.
+    pub async fn run<T, F>(&self, command: F) -> Result<T, ZBBError>
+    where
+        T: Send + 'static,
+        F: FnMut(&AdbConnection) -> Result<T, ZBBError> + Send + 'static,
+    {
+        let timeout = Duration::from_millis(self.timeouts.lock().unwrap().command);
+
+        self.run_with_timeout(&self.permits, timeout, command).await
+    }
+
+    /// Same as [AdbClient::run], but with the transfer timeout for commands that stay
+    /// silent for a long time, like `pm install`. Transfers have their own permits.
+    pub async fn run_transfer<T, F>(&self, command: F) -> Result<T, ZBBError>
+    where
+        T: Send + 'static,
+        F: FnMut(&AdbConnection) -> Result<T, ZBBError> + Send + 'static,
+    {
+        let timeout = Duration::from_millis(self.timeouts.lock().unwrap().transfer);
+
+        self.run_with_timeout(&self.transfer_permits, timeout, command).await
+    }
+
+    async fn run_with_timeout<T, F>(
+        &self,
+        permits: &Arc<Semaphore>,
+        timeout: Duration,
+        mut command: F,
+    ) -> Result<T, ZBBError>
+    where
+        T: Send + 'static,
+        F: FnMut(&AdbConnection) -> Result<T, ZBBError> + Send + 'static,
+    {
+        // The permit goes along to the blocking thread, so a command that outlives its
+        // caller still counts against the limit
+        let permit = permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|err| ZBBError::Other(err.to_string()))?;
+
+        let paths = self.paths.clone();
+        let server = self.server();
+
+        tauri::async_runtime::spawn_blocking(move || {
+            let _permit = permit;
+            let connection = AdbConnection::new(&server, timeout);
+
+            match command(&connection) {
+                Err(ZBBError::ServerUnavailable) => {
+                    warn!("Unable to reach adb server {:?}", server);
+
+                    restart(&paths, &server)?;
+                    command(&connection)
+                }
+                result => result,
+            }
+        })
+        .await
+        .map_err(|err| ZBBError::Other(err.to_string()))?
+    }
+}
+
+/// Client side of the ADB host protocol.
+///
+/// The server answers a single request per socket, so every call opens a new one.
+pub struct AdbConnection {
+    address: SocketAddr,
+    timeout: Duration,
+}
+
+impl AdbConnection {
+    fn new(server: &AdbServer, timeout: Duration) -> Self {
+        Self {
+            address: SocketAddr::from((server.host, server.port)),
+            timeout,
+        }
+    }
+
+    pub fn devices(&self) -> Result<Vec<LocalDevice>, ZBBError> {
+        Ok(parse_device_list(&self.query("host:devices")?))
+    }
+
+    pub fn devices_long(&self) -> Result<Vec<LocalDeviceLong>, ZBBError> {
+        Ok(parse_long_device_list(&self.query("host:devices-l")?))
+    }
+
+    pub fn connect(&self, ip: Ipv4Addr, port: u16) -> Result<(), ZBBError> {
+        let message = self.query(&format!("host:connect:{}:{}", ip, port))?;
+
+        // The server answers OKAY even if the device couldn't be reached
+        if message.starts_with("connected to") || message.starts_with("already connected to") {
+            Ok(())
+        } else {
+            Err(ZBBError::ADB(message))
+        }
+    }
+
+    pub fn kill(&self) -> Result<(), ZBBError> {
+        send_host_request(&mut self.open()?, "host:kill")
+    }
+
+    /// Runs [command] (see [crate::adb::ShellCommand]) on the device [serial], or on the
+    /// only device if there is no serial, and returns its output.
+    pub fn shell_command(&self, serial: &Option<String>, command: Vec<String>) -> Result<Vec<u8>, ZBBError> {
+        let mut stream = self.open()?;
+
+        match serial {
+            Some(serial) => send_host_request(&mut stream, &format!("host:transport:{}", serial))?,
+            None => send_host_request(&mut stream, "host:transport-any")?,
+        }
+        send_host_request(&mut stream, &format!("shell:{}", command.join(" ")))?;
+
+        let mut output = vec![];
+        stream.read_to_end(&mut output)?;
+
+        Ok(output)
+    }
+
+    fn query(&self, request: &str) -> Result<String, ZBBError> {
+        let mut stream = self.open()?;
+        send_host_request(&mut stream, request)?;
+
+        read_host_message(&mut stream)
+    }
+
+    fn open(&self) -> Result<TcpStream, ZBBError> {
+        let stream = TcpStream::connect_timeout(&self.address, self.timeout).map_err(|error| match error.kind() {
+            ErrorKind::TimedOut | ErrorKind::WouldBlock => ZBBError::Timeout,
+            _ => ZBBError::ServerUnavailable,
+        })?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        Ok(stream)
+    }
+}
+
+/// Relaunches a local server. A remote one is left alone, but the caller still
+/// retries on a fresh connection, e.g. after the remote server has been restarted.
+fn restart(paths: &Paths, server: &AdbServer) -> Result<(), ZBBError> {
+    launch_adb(paths, server).map_err(|error| {
+        warn!("Unable to launch adb: {:?}", error);
+        ZBBError::ServerUnavailable
+    })
+}
@@ -1,13 +1,58 @@
 use async_std::io::{ReadExt, WriteExt};
+use tauri::State;
 
-use crate::structs::{AppPhase, ZBBError};
+use crate::forward::forwarded_port;
+use crate::operations::Operations;
+use crate::client::AdbClient;
+use crate::structs::{AppPhase, PhaseTransport, ZBBError};
 
 const SOCKET_PORT: u16 = 1337;
 
+/// Reads the phase of the app on the headset [ip]. With [PhaseTransport::Forward], [ip]
+/// is ignored and the socket is reached through `adb forward` instead.
 #[tauri::command]
-pub async fn get_phase(ip: String) -> Result<AppPhase, ZBBError> {
-    let ip_address = format!("{}:{}", ip, SOCKET_PORT);
-    let mut socket = async_std::net::TcpStream::connect(ip_address).await?;
+pub async fn get_phase<'a>(
+    ip: String,
+    transport: Option<PhaseTransport>,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<AppPhase, ZBBError> {
+    operations.run(&ip, async {
+        let address = socket_address(&ip, transport, &client).await?;
+
+        read_phase(&address).await
+    }).await
+}
+
+#[tauri::command]
+pub async fn set_phase<'a>(
+    ip: String,
+    phase: AppPhase,
+    transport: Option<PhaseTransport>,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<(), ZBBError> {
+    operations.run(&ip, async {
+        let address = socket_address(&ip, transport, &client).await?;
+
+        write_phase(&address, phase).await
+    }).await
+}
+
+async fn socket_address(ip: &str, transport: Option<PhaseTransport>, client: &AdbClient) -> Result<String, ZBBError> {
+    match transport.unwrap_or(PhaseTransport::Network) {
+        PhaseTransport::Network => Ok(format!("{}:{}", ip, SOCKET_PORT)),
+        // The forwarded port is opened on the machine of the ADB server
+        PhaseTransport::Forward { serial } => {
+            let port = forwarded_port(client, &serial, SOCKET_PORT).await?;
+
+            Ok(format!("{}:{}", client.server().host, port))
+        }
+    }
+}
+
+async fn read_phase(address: &str) -> Result<AppPhase, ZBBError> {
+    let mut socket = async_std::net::TcpStream::connect(address).await?;
 
     let mut buffer = [0u8; 128];
     socket.write(b"get_phase").await?;
@@ -20,10 +65,8 @@ pub async fn get_phase(ip: String) -> Result<AppPhase, ZBBError> {
         .map_err(|_| ZBBError::IO("Konnte Status nicht lesen.".into()))
 }
 
-#[tauri::command]
-pub async fn set_phase(ip: String, phase: AppPhase) -> Result<(), ZBBError> {
-    let ip_address = format!("{}:{}", ip, SOCKET_PORT);
-    let mut socket = async_std::net::TcpStream::connect(ip_address).await?;
+async fn write_phase(address: &str, phase: AppPhase) -> Result<(), ZBBError> {
+    let mut socket = async_std::net::TcpStream::connect(address).await?;
 
     let payload = format!("set_phase {}", phase);
     socket.write(payload.as_bytes()).await?;
@@ -46,7 +89,7 @@ mod tests {
     #[tokio::test]
     #[ignore]
     async fn test_set_phase() {
-        let result = set_phase("127.0.0.1".to_string(), AppPhase::Windup).await;
+        let result = write_phase("127.0.0.1:1337", AppPhase::Windup).await;
 
         println!("{:?}", result);
         assert!(result.is_ok());
@@ -55,7 +98,7 @@ mod tests {
     #[tokio::test]
     #[ignore]
     async fn test_get_phase() {
-        let result = get_phase("127.0.0.1".to_string()).await;
+        let result = read_phase("127.0.0.1:1337").await;
 
         println!("{:?}", result);
         assert_eq!(result.unwrap(), AppPhase::Onboarding);
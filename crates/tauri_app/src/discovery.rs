@@ -0,0 +1,196 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use log::{info, warn};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Semaphore;
+use crate::client::AdbClient;
+use crate::structs::{DiscoveredDevice, ZBBError};
+use crate::util::{local_subnets, output_with_timeout, subnet_hosts};
+
+/// Emitted with the full `Vec<DiscoveredDevice>` whenever a headset appears on or leaves the network.
+pub const DISCOVERED_DEVICES_EVENT: &str = "discovered-devices";
+const DISCOVERY_INTERVAL: Duration = Duration::from_millis(5000);
+/// `adb mdns services` only reads the cache of the server, so it shouldn't take long.
+const BROWSE_TIMEOUT: Duration = Duration::from_millis(3000);
+/// mDNS service types of devices that accept ADB connections over Wi-Fi.
+const SERVICE_TYPES: [&str; 2] = ["_adb-tls-connect._tcp", "_adb._tcp"];
+/// How long a probed address gets to accept the connection.
+const SCAN_TIMEOUT: Duration = Duration::from_millis(500);
+const SCAN_PARALLELISM: usize = 64;
+/// Subnets with a shorter prefix (more than 1022 hosts) are too large to be scanned,
+/// only the /24 around the host is probed then.
+const MIN_SCAN_PREFIX: u32 = 22;
+
+/// Headsets currently advertised on the local network.
+#[derive(Default)]
+pub struct Discovery {
+    devices: Mutex<Vec<DiscoveredDevice>>,
+    /// Incremented by every start and stop, a browsing thread ends once it's outdated.
+    generation: AtomicU64,
+}
+
+/// Starts browsing the ADB mDNS services through the ADB server, [DISCOVERED_DEVICES_EVENT]
+/// is emitted whenever the list changes. Runs until [stop_discovery] is called.
+#[tauri::command]
+pub async fn start_discovery(app: AppHandle, discovery: State<'_, Discovery>) -> Result<(), ZBBError> {
+    let generation = discovery.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+    std::thread::spawn(move || {
+        while app.state::<Discovery>().generation.load(Ordering::SeqCst) == generation {
+            discover(&app);
+            std::thread::sleep(DISCOVERY_INTERVAL);
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_discovery(discovery: State<'_, Discovery>) -> Result<(), ZBBError> {
+    discovery.generation.fetch_add(1, Ordering::SeqCst);
+    discovery.devices.lock().unwrap().clear();
+
+    Ok(())
+}
+
+fn discover(app: &AppHandle) {
+    match browse(&app.state::<AdbClient>()) {
+        Ok(devices) => {
+            let discovery = app.state::<Discovery>();
+            let mut known = discovery.devices.lock().unwrap();
+
+            if *known != devices {
+                info!("Discovered devices: {:?}", devices);
+                let _ = app.emit_all(DISCOVERED_DEVICES_EVENT, &devices);
+                *known = devices;
+            }
+        }
+        Err(error) => warn!("Unable to browse mdns services: {:?}", error),
+    }
+}
+
+#[tauri::command]
+pub async fn get_discovered_devices(discovery: State<'_, Discovery>) -> Result<Vec<DiscoveredDevice>, ZBBError> {
+    Ok(discovery.devices.lock().unwrap().clone())
+}
+
+/// Probes every address in the subnets of the host for an open [port], as a fallback
+/// for headsets that don't show up via mDNS.
+///
+/// Returns the addresses that accepted a connection, to be passed to `connect_to_ip`.
+#[tauri::command]
+pub async fn scan_network(port: u16) -> Result<Vec<String>, ZBBError> {
+    let hosts = local_subnets()?
+        .into_iter()
+        .flat_map(|(ip, netmask)| subnet_hosts(ip, scan_netmask(ip, netmask)))
+        .collect::<Vec<_>>();
+    info!("Scanning {} addresses for port {}", hosts.len(), port);
+
+    let permits = Arc::new(Semaphore::new(SCAN_PARALLELISM));
+    let probes = hosts
+        .into_iter()
+        .map(|host| {
+            let permits = permits.clone();
+
+            tauri::async_runtime::spawn(async move {
+                let _permit = permits.acquire_owned().await.ok()?;
+
+                is_port_open(host, port).await.then_some(host)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut found = vec![];
+    for probe in probes {
+        if let Ok(Some(host)) = probe.await {
+            found.push(host.to_string());
+        }
+    }
+
+    info!("Found port {} open on {:?}", port, found);
+    Ok(found)
+}
+
+fn scan_netmask(ip: Ipv4Addr, netmask: Ipv4Addr) -> Ipv4Addr {
+    if u32::from(netmask).count_ones() >= MIN_SCAN_PREFIX {
+        return netmask;
+    }
+
+    info!("Subnet of {} ({}) is too large, scanning its /24 only", ip, netmask);
+    Ipv4Addr::new(255, 255, 255, 0)
+}
+
+async fn is_port_open(host: Ipv4Addr, port: u16) -> bool {
+    let address = SocketAddr::from((host, port));
+
+    matches!(
+        async_std::future::timeout(SCAN_TIMEOUT, async_std::net::TcpStream::connect(address)).await,
+        Ok(Ok(_))
+    )
+}
+
+fn browse(client: &AdbClient) -> Result<Vec<DiscoveredDevice>, ZBBError> {
+    let mut command = client.command()?;
+    command.args(vec!["mdns".to_string(), "services".to_string()]);
+    let output = output_with_timeout(command, BROWSE_TIMEOUT)?;
+
+    Ok(parse_services(&String::from_utf8(output.stdout)?))
+}
+
+/// Parses the `<name>\t<service type>\t<ip>:<port>` lines of `adb mdns services`.
+fn parse_services(output: &str) -> Vec<DiscoveredDevice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let columns = line.split_whitespace().collect::<Vec<_>>();
+            let [name, service_type, address] = columns[..] else { return None };
+
+            let service_type = service_type.trim_end_matches('.');
+            if !SERVICE_TYPES.contains(&service_type) {
+                return None;
+            }
+
+            let (address, port) = address.rsplit_once(':')?;
+
+            Some(DiscoveredDevice {
+                name: name.to_string(),
+                service_type: service_type.to_string(),
+                address: address.to_string(),
+                port: port.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_netmask() {
+        let ip = Ipv4Addr::new(10, 0, 4, 20);
+
+        assert_eq!(Ipv4Addr::new(255, 255, 252, 0), scan_netmask(ip, Ipv4Addr::new(255, 255, 252, 0)));
+        assert_eq!(Ipv4Addr::new(255, 255, 255, 0), scan_netmask(ip, Ipv4Addr::new(255, 255, 0, 0)));
+    }
+
+    #[test]
+    fn test_parse_services() {
+        let output = "List of discovered mdns services
+adb-1WMHH8000000-AbCdEf\t_adb-tls-connect._tcp\t192.168.1.20:37123
+adb-1WMHH8000000-AbCdEf\t_adb-tls-pairing._tcp\t192.168.1.20:41234
+adb-2G0YC1ZF-XyZ\t_adb._tcp.\t192.168.1.21:5555
+";
+        let devices = parse_services(output);
+
+        assert_eq!(2, devices.len());
+        assert_eq!("adb-1WMHH8000000-AbCdEf", devices[0].name);
+        assert_eq!("192.168.1.20", devices[0].address);
+        assert_eq!(37123, devices[0].port);
+        assert_eq!("_adb._tcp", devices[1].service_type);
+        assert_eq!(5555, devices[1].port);
+    }
+}
@@ -0,0 +1,195 @@
+use log::info;
+use tauri::State;
+use crate::operations::Operations;
+use crate::client::AdbClient;
+use crate::structs::{PortForward, ZBBError};
+
+/// Forwards [local] on the machine of the ADB server to [remote] on the device.
+///
+/// Without [local], adb picks a free port. Returns the local port.
+#[tauri::command]
+pub async fn forward_port<'a>(
+    id: String,
+    local: Option<u16>,
+    remote: u16,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<u16, ZBBError> {
+    operations.run(&id, forward(&client, &id, local, remote)).await
+}
+
+#[tauri::command]
+pub async fn remove_forward<'a>(
+    id: String,
+    local: u16,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<(), ZBBError> {
+    operations.run(&id, client.run_adb(vec![
+        "-s".to_string(),
+        id.clone(),
+        "forward".to_string(),
+        "--remove".to_string(),
+        format!("tcp:{}", local),
+    ])).await?;
+
+    Ok(())
+}
+
+/// Lists the forwards of all devices, or only those of [id].
+#[tauri::command]
+pub async fn list_forwards<'a>(
+    id: Option<String>,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<Vec<PortForward>, ZBBError> {
+    let forwards = operations.deadline(read_forwards(&client)).await?;
+
+    Ok(forwards
+        .into_iter()
+        .filter(|forward| id.as_ref().is_none_or(|id| &forward.serial == id))
+        .collect())
+}
+
+/// Lets the device reach [local] on the machine of the ADB server through [remote].
+#[tauri::command]
+pub async fn reverse_port<'a>(
+    id: String,
+    remote: u16,
+    local: u16,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<(), ZBBError> {
+    operations.run(&id, client.run_adb(vec![
+        "-s".to_string(),
+        id.clone(),
+        "reverse".to_string(),
+        format!("tcp:{}", remote),
+        format!("tcp:{}", local),
+    ])).await?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_reverse<'a>(
+    id: String,
+    remote: u16,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<(), ZBBError> {
+    operations.run(&id, client.run_adb(vec![
+        "-s".to_string(),
+        id.clone(),
+        "reverse".to_string(),
+        "--remove".to_string(),
+        format!("tcp:{}", remote),
+    ])).await?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_reverses<'a>(
+    id: String,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<Vec<PortForward>, ZBBError> {
+    let output = operations.run(&id, client.run_adb(vec![
+        "-s".to_string(),
+        id.clone(),
+        "reverse".to_string(),
+        "--list".to_string(),
+    ])).await?;
+
+    // The first column is the transport (e.g. `UsbFfs`), not the serial
+    Ok(parse_forward_list(&String::from_utf8(output.stdout)?)
+        .into_iter()
+        .map(|reverse| PortForward {
+            serial: id.clone(),
+            ..reverse
+        })
+        .collect())
+}
+
+/// Returns the local port that is forwarded to [remote] on the device, setting up a
+/// forward if there is none yet.
+pub async fn forwarded_port(client: &AdbClient, id: &str, remote: u16) -> Result<u16, ZBBError> {
+    let remote_spec = format!("tcp:{}", remote);
+
+    let existing = read_forwards(client)
+        .await?
+        .into_iter()
+        .find(|forward| forward.serial == id && forward.remote == remote_spec)
+        .and_then(|forward| forward.local.strip_prefix("tcp:")?.parse().ok());
+
+    match existing {
+        Some(port) => Ok(port),
+        None => forward(client, id, None, remote).await,
+    }
+}
+
+async fn forward(client: &AdbClient, id: &str, local: Option<u16>, remote: u16) -> Result<u16, ZBBError> {
+    let output = client.run_adb(vec![
+        "-s".to_string(),
+        id.to_string(),
+        "forward".to_string(),
+        format!("tcp:{}", local.unwrap_or(0)),
+        format!("tcp:{}", remote),
+    ]).await?;
+
+    // adb only prints the port if it picked one itself
+    let port = match local {
+        Some(port) => port,
+        None => String::from_utf8(output.stdout)?
+            .trim()
+            .parse()
+            .map_err(|err| ZBBError::ADB(format!("Invalid forward port: {:?}", err)))?,
+    };
+    info!("Forwarding tcp:{} to tcp:{} on {}", port, remote, id);
+
+    Ok(port)
+}
+
+async fn read_forwards(client: &AdbClient) -> Result<Vec<PortForward>, ZBBError> {
+    let output = client.run_adb(vec!["forward".to_string(), "--list".to_string()]).await?;
+
+    Ok(parse_forward_list(&String::from_utf8(output.stdout)?))
+}
+
+/// Parses the `<serial> <local> <remote>` lines of `adb forward --list` and `adb reverse --list`.
+fn parse_forward_list(output: &str) -> Vec<PortForward> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let columns = line.split_whitespace().collect::<Vec<_>>();
+            let [serial, local, remote] = columns[..] else { return None };
+
+            Some(PortForward {
+                serial: serial.to_string(),
+                local: local.to_string(),
+                remote: remote.to_string(),
+            })
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_forward_list() {
+        let output = "1WMHH8000000 tcp:41337 tcp:1337
+192.168.1.20:5555 tcp:8080 localabstract:scrcpy
+";
+        let forwards = parse_forward_list(output);
+
+        assert_eq!(2, forwards.len());
+        assert_eq!("1WMHH8000000", forwards[0].serial);
+        assert_eq!("tcp:41337", forwards[0].local);
+        assert_eq!("tcp:1337", forwards[0].remote);
+        assert_eq!("localabstract:scrcpy", forwards[1].remote);
+    }
+}
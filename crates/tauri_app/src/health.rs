@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use tauri::State;
+use crate::adb::ShellCommand;
+use crate::operations::Operations;
+use crate::client::AdbClient;
+use crate::structs::{DeviceHealth, ThermalStatus, ZBBError};
+
+/// `Temperature.TYPE_CPU` of the thermal HAL.
+const TEMPERATURE_TYPE_CPU: i32 = 0;
+
+/// Gets storage, memory, thermal state and uptime of the device.
+#[tauri::command]
+pub async fn get_device_health<'a>(
+    id: String,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<DeviceHealth, ZBBError> {
+    let serial = Some(id.clone());
+
+    let (df, meminfo, thermal, uptime) = operations.run(&id, client.run(move |adb| {
+        let df = adb.shell_command(&serial, ShellCommand::new("df").arg("/data").build())?;
+        let meminfo = adb.shell_command(&serial, ShellCommand::new("cat").arg("/proc/meminfo").build())?;
+        let thermal = adb.shell_command(&serial, ShellCommand::new("dumpsys").arg("thermalservice").build())?;
+        let uptime = adb.shell_command(&serial, ShellCommand::new("cat").arg("/proc/uptime").build())?;
+
+        Ok((df, meminfo, thermal, uptime))
+    })).await?;
+
+    let (storage_total, storage_free) = parse_df(&String::from_utf8(df)?)?;
+    let (memory_total, memory_free) = parse_meminfo(&String::from_utf8(meminfo)?)?;
+    let (thermal_status, throttling, cpu_temperature) = parse_thermal(&String::from_utf8(thermal)?);
+
+    Ok(DeviceHealth {
+        storage_free,
+        storage_total,
+        memory_free,
+        memory_total,
+        thermal_status,
+        throttling,
+        cpu_temperature,
+        uptime: parse_uptime(&String::from_utf8(uptime)?)?,
+    })
+}
+
+/// Parses total and available bytes from the last line of `df`, which reports 1K blocks:
+/// `<filesystem> <1K-blocks> <used> <available> <use%> <mounted on>`.
+fn parse_df(output: &str) -> Result<(u64, u64), ZBBError> {
+    let columns = output
+        .lines()
+        .last()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>();
+    let kilobytes = |index: usize| columns.get(index).and_then(|value| value.parse::<u64>().ok());
+
+    match (kilobytes(1), kilobytes(3)) {
+        (Some(total), Some(available)) => Ok((total * 1024, available * 1024)),
+        _ => Err(ZBBError::Other(format!("Unable to read storage: {}", output.trim()))),
+    }
+}
+
+/// Parses `MemTotal` and `MemAvailable` of `/proc/meminfo` in bytes.
+fn parse_meminfo(output: &str) -> Result<(u64, u64), ZBBError> {
+    let values = output
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter_map(|(key, value)| {
+            let kilobytes = value.trim().trim_end_matches("kB").trim().parse::<u64>().ok()?;
+
+            Some((key.trim(), kilobytes * 1024))
+        })
+        .collect::<HashMap<_, _>>();
+
+    match (values.get("MemTotal"), values.get("MemAvailable")) {
+        (Some(total), Some(available)) => Ok((*total, *available)),
+        _ => Err(ZBBError::Other("Unable to read memory".into())),
+    }
+}
+
+/// Parses `dumpsys thermalservice`, which has a `Thermal Status: <n>` line and lists the
+/// sensors as `Temperature{mValue=41.2, mType=0, mName=cpu0, mStatus=1}`.
+///
+/// Returns the overall status, the highest status of a sensor and the hottest CPU sensor.
+fn parse_thermal(dump: &str) -> (ThermalStatus, ThermalStatus, Option<f32>) {
+    let status = dump
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Thermal Status:"))
+        .and_then(|status| status.trim().parse::<i32>().ok())
+        .unwrap_or_default();
+
+    let temperatures = dump
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Temperature{")?.strip_suffix('}'))
+        .map(|fields| {
+            fields
+                .split(',')
+                .filter_map(|field| field.split_once('='))
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .collect::<HashMap<_, _>>()
+        })
+        .collect::<Vec<_>>();
+
+    let throttling = temperatures
+        .iter()
+        .filter_map(|temperature| temperature.get("mStatus")?.parse::<i32>().ok())
+        .max()
+        .unwrap_or_default();
+
+    let cpu_temperature = temperatures
+        .iter()
+        .filter(|temperature| temperature.get("mType").and_then(|it| it.parse::<i32>().ok()) == Some(TEMPERATURE_TYPE_CPU))
+        .filter_map(|temperature| temperature.get("mValue")?.parse::<f32>().ok())
+        .reduce(f32::max);
+
+    (status.into(), throttling.into(), cpu_temperature)
+}
+
+/// Parses the seconds since boot, the first value of `/proc/uptime`.
+fn parse_uptime(output: &str) -> Result<u64, ZBBError> {
+    output
+        .split_whitespace()
+        .next()
+        .and_then(|seconds| seconds.parse::<f64>().ok())
+        .map(|seconds| seconds as u64)
+        .ok_or(ZBBError::Other(format!("Unable to read uptime: {}", output.trim())))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_df() {
+        let output = "Filesystem        1K-blocks    Used Available Use% Mounted on
+/dev/block/dm-5  115178372 20134216  94913084  18% /data
+";
+
+        assert_eq!((115178372 * 1024, 94913084 * 1024), parse_df(output).unwrap());
+        assert!(parse_df("df: /data: Permission denied").is_err());
+    }
+
+    #[test]
+    fn test_parse_meminfo() {
+        let output = "MemTotal:        5818744 kB
+MemFree:          245512 kB
+MemAvailable:    2403344 kB
+";
+
+        assert_eq!((5818744 * 1024, 2403344 * 1024), parse_meminfo(output).unwrap());
+    }
+
+    #[test]
+    fn test_parse_thermal() {
+        let dump = "IsStatusOverride: false
+ThermalEventListeners:
+\tcallbacks: 2
+Thermal Status: 2
+Cached temperatures:
+\tTemperature{mValue=38.5, mType=2, mName=battery, mStatus=0}
+HAL Ready: true
+Current temperatures from HAL:
+\tTemperature{mValue=52.1, mType=0, mName=cpu0, mStatus=1}
+\tTemperature{mValue=55.4, mType=0, mName=cpu1, mStatus=2}
+\tTemperature{mValue=41.0, mType=1, mName=gpu, mStatus=0}
+";
+        let (status, throttling, cpu_temperature) = parse_thermal(dump);
+
+        assert_eq!(ThermalStatus::Moderate, status);
+        assert_eq!(ThermalStatus::Moderate, throttling);
+        assert_eq!(Some(55.4), cpu_temperature);
+    }
+
+    #[test]
+    fn test_parse_uptime() {
+        assert_eq!(86523, parse_uptime("86523.71 338912.30\n").unwrap());
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Stdio};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use log::{info, warn};
+use tauri::{AppHandle, Manager, State};
+use crate::adb::ShellCommand;
+use crate::operations::Operations;
+use crate::client::AdbClient;
+use crate::structs::{LogcatLines, ZBBError};
+use crate::util::sanitize_file_name;
+
+/// Emitted with [LogcatLines] at most every [LOGCAT_BATCH_INTERVAL] per streamed device.
+pub const LOGCAT_EVENT: &str = "logcat-lines";
+/// A busy headset logs hundreds of lines per second, one event each would flood the webview.
+const LOGCAT_BATCH_INTERVAL: Duration = Duration::from_millis(100);
+/// Size after which a logcat file is rotated.
+const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+/// Number of rotated files kept next to the current one.
+const MAX_ROTATED_FILES: usize = 3;
+
+/// Running `adb logcat` processes by serial.
+#[derive(Default)]
+pub struct LogcatManager {
+    streams: Mutex<HashMap<String, Child>>,
+}
+
+impl LogcatManager {
+    fn stop(&self, id: &str) {
+        if let Some(mut child) = self.streams.lock().unwrap().remove(id) {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+
+    /// Kills all streams, e.g. when the app exits.
+    pub fn stop_all(&self) {
+        for (_, mut child) in self.streams.lock().unwrap().drain() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+
+    /// Removes the stream of [id] after it ended by itself, unless it has been replaced already.
+    fn ended(&self, id: &str, pid: u32) {
+        let mut streams = self.streams.lock().unwrap();
+        if streams.get(id).map(|child| child.id()) == Some(pid) {
+            if let Some(mut child) = streams.remove(id) {
+                let _ = child.wait();
+            }
+        }
+    }
+}
+
+/// Starts streaming the logs of [package] on the device.
+///
+/// Only lines of the app's current process are forwarded, so the stream has to be
+/// restarted after the app has been relaunched.
+#[tauri::command]
+pub async fn start_logcat<'a>(
+    id: String,
+    package: String,
+    app: AppHandle,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+    logcat: State<'a, LogcatManager>,
+) -> Result<(), ZBBError> {
+    let serial = Some(id.clone());
+    let package = package.split('/').next().unwrap_or_default().to_string();
+
+    let pidof = ShellCommand::new("pidof").package(&package)?.build();
+
+    let pid_bytes = operations.run(&id, client.run(move |adb| adb.shell_command(&serial, pidof.clone()))).await?;
+    let pid = String::from_utf8(pid_bytes)?
+        .split_whitespace()
+        .next()
+        .map(|pid| pid.to_string())
+        .ok_or(ZBBError::Other(format!("{} is not running", package)))?;
+
+    let log_dir = app
+        .path_resolver()
+        .app_log_dir()
+        .ok_or(ZBBError::Other("No log directory".into()))?;
+    let mut file = RotatingFile::new(log_dir.join(format!("logcat_{}.log", sanitize_file_name(&id))), MAX_FILE_SIZE)?;
+
+    let mut child = client
+        .command()?
+        .args(vec![
+            "-s".to_string(),
+            id.clone(),
+            "logcat".to_string(),
+            "-v".to_string(),
+            "threadtime".to_string(),
+            format!("--pid={}", pid),
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or(ZBBError::Other("No logcat output".into()))?;
+
+    let process_id = child.id();
+    logcat.stop(&id);
+    logcat.streams.lock().unwrap().insert(id.clone(), child);
+    info!("Streaming logcat of {} (pid {})", id, pid);
+
+    let (sender, receiver) = mpsc::channel::<String>();
+    let serial = id.clone();
+    let emitter = app.clone();
+    std::thread::spawn(move || {
+        // Wait for a line, then collect whatever else arrives within the interval
+        while let Ok(line) = receiver.recv() {
+            std::thread::sleep(LOGCAT_BATCH_INTERVAL);
+
+            let mut lines = vec![line];
+            lines.extend(receiver.try_iter());
+            let _ = emitter.emit_all(LOGCAT_EVENT, LogcatLines {
+                serial: serial.clone(),
+                lines,
+            });
+        }
+    });
+
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else { break };
+
+            if let Err(error) = file.write_line(&line) {
+                warn!("Unable to write logcat of {}: {:?}", id, error);
+            }
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+
+        info!("Logcat of {} ended", id);
+        app.state::<LogcatManager>().ended(&id, process_id);
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_logcat(id: String, logcat: State<'_, LogcatManager>) -> Result<(), ZBBError> {
+    logcat.stop(&id);
+
+    Ok(())
+}
+
+/// Log file that is moved to `<name>.1`, `<name>.2`, … once it exceeds [max_size] bytes.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+}
+
+impl RotatingFile {
+    fn new(path: PathBuf, max_size: u64) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self { path, file, size, max_size })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.size >= self.max_size {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        for index in (1..MAX_ROTATED_FILES).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                std::fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, rotated_path(&self.path, 1))?;
+
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", index));
+
+    path.with_file_name(name)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation() {
+        let dir = std::env::temp_dir().join(format!("zbbvrui_logcat_{}", std::process::id()));
+        let path = dir.join("logcat_test.log");
+        let line = "x".repeat(1023);
+
+        let mut file = RotatingFile::new(path.clone(), 4 * 1024).unwrap();
+        for _ in 0..4 * (MAX_ROTATED_FILES + 2) {
+            file.write_line(&line).unwrap();
+        }
+
+        assert!(path.exists());
+        assert!(rotated_path(&path, MAX_ROTATED_FILES).exists());
+        assert!(!rotated_path(&path, MAX_ROTATED_FILES + 1).exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
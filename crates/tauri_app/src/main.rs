@@ -1,6 +1,7 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use log::info;
 use system_shutdown::shutdown;
@@ -10,14 +11,34 @@ use tauri_plugin_log::LogTarget;
 use window_manager::WindowError;
 
 use crate::adb::*;
+use crate::batch::*;
 use crate::communication::{get_phase, set_phase};
+use crate::discovery::{get_discovered_devices, scan_network, start_discovery, stop_discovery, Discovery};
+use crate::forward::*;
+use crate::health::get_device_health;
+use crate::logcat::{start_logcat, stop_logcat, LogcatManager};
+use crate::operations::{cancel_operations, get_timeouts, set_timeouts, Operations};
+use crate::screen::*;
+use crate::client::{AdbClient, MAX_ADB_CONNECTIONS, MAX_ADB_TRANSFERS};
+use crate::registry::{get_registered_devices, remove_registered_device, save_registered_device, Registry};
 use crate::structs::*;
 use crate::util::*;
+use crate::wifi::provision_wifi;
 
 mod adb;
+mod batch;
+mod client;
+mod registry;
 mod structs;
 mod util;
 mod communication;
+mod discovery;
+mod forward;
+mod health;
+mod logcat;
+mod operations;
+mod screen;
+mod wifi;
 
 #[tauri::command]
 async fn get_window_position(pid: u32) -> Result<window_manager::Position, WindowError> {
@@ -61,23 +82,63 @@ fn main() {
     tauri::Builder::default()
         .invoke_handler(tauri::generate_handler![
             get_devices,
+            get_devices_long,
             connect_device,
             connect_to_ip,
+            pair_device,
+            get_known_addresses,
+            forget_device,
+            get_registered_devices,
+            save_registered_device,
+            remove_registered_device,
+            get_discovered_devices,
+            start_discovery,
+            stop_discovery,
+            scan_network,
             get_ip,
+            get_wifi_status,
+            provision_wifi,
             get_adb_path,
             get_scrcpy_path,
             get_window_position,
             set_window_position,
             is_running,
+            get_app_state,
             launch_app,
+            install_apk,
+            uninstall_app,
+            list_packages,
             shutdown_device,
+            reboot_device,
+            disconnect_device,
+            launch_app_batch,
+            kill_app_batch,
+            shutdown_device_batch,
             get_battery_level,
+            get_controller_status,
+            get_device_health,
             is_screen_on,
             kill_server,
+            get_adb_server,
+            set_adb_server,
             kill_app,
             shutdown_host,
             get_phase,
-            set_phase
+            set_phase,
+            forward_port,
+            remove_forward,
+            list_forwards,
+            reverse_port,
+            remove_reverse,
+            list_reverses,
+            start_logcat,
+            stop_logcat,
+            capture_screenshot,
+            start_recording,
+            stop_recording,
+            cancel_operations,
+            get_timeouts,
+            set_timeouts
         ])
         .plugin(
             tauri_plugin_log::Builder::default()
@@ -89,13 +150,41 @@ fn main() {
                 find_binary("adb", app.handle(), true),
                 find_binary("scrcpy", app.handle(), !is_windows()),
             );
+            let server = load_adb_server(&app.handle());
+            launch_adb(&paths, &server).expect("Unable to start ADB");
+            let timeouts = Arc::new(Mutex::new(Timeouts::default()));
+            app.manage(AdbClient::new(
+                paths.clone(),
+                server,
+                timeouts.clone(),
+                MAX_ADB_CONNECTIONS,
+                MAX_ADB_TRANSFERS,
+            ));
+            app.manage(Operations::new(timeouts));
+            app.manage(DeviceTracker::default());
+            app.manage(LogcatManager::default());
+            app.manage(RecordingManager::default());
+            app.manage(Discovery::default());
+            app.manage(Supervisor::load(
+                app.path_resolver().app_data_dir().map(|dir| dir.join("known_addresses.json")),
+            ));
+            app.manage(Registry::load(
+                app.path_resolver().app_data_dir().map(|dir| dir.join("device_registry.json")),
+            ));
             let res = app.manage(paths);
             info!("{}", res);
             info!("{:?}", app.state::<Paths>());
 
-            launch_adb(app.state());
+            spawn_device_tracker(app.handle());
+            spawn_supervisor(app.handle());
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // The adb logcat processes would outlive the app otherwise
+            if let tauri::RunEvent::Exit = event {
+                app.state::<LogcatManager>().stop_all();
+            }
+        });
 }
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use log::info;
+use tauri::State;
+use tokio::sync::Notify;
+use crate::structs::{Timeouts, ZBBError};
+
+/// Deadlines and cancellation of device commands.
+///
+/// Cancelling only abandons the command on the host side: the UI gets its answer right
+/// away, while an ADB call that already reached the device still runs to completion.
+pub struct Operations {
+    /// Shared with the [crate::client::AdbClient], which puts them on its sockets.
+    timeouts: Arc<Mutex<Timeouts>>,
+    cancellations: Mutex<HashMap<String, Arc<Notify>>>,
+}
+
+impl Operations {
+    pub fn new(timeouts: Arc<Mutex<Timeouts>>) -> Self {
+        Self {
+            timeouts,
+            cancellations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Runs [operation] for the device [id], failing with [ZBBError::Timeout] once the
+    /// command timeout is up or with [ZBBError::Cancelled] if [cancel_operations] is called.
+    pub async fn run<T>(&self, id: &str, operation: impl Future<Output = Result<T, ZBBError>>) -> Result<T, ZBBError> {
+        let timeout = Duration::from_millis(self.timeouts.lock().unwrap().command);
+
+        self.run_with_timeout(id, timeout, operation).await
+    }
+
+    /// Same as [Operations::run], but with the longer timeout for file transfers and installs.
+    pub async fn run_transfer<T>(&self, id: &str, operation: impl Future<Output = Result<T, ZBBError>>) -> Result<T, ZBBError> {
+        let timeout = Duration::from_millis(self.timeouts.lock().unwrap().transfer);
+
+        self.run_with_timeout(id, timeout, operation).await
+    }
+
+    /// Puts the command timeout on an [operation] that doesn't belong to a single device.
+    pub async fn deadline<T>(&self, operation: impl Future<Output = Result<T, ZBBError>>) -> Result<T, ZBBError> {
+        let timeout = Duration::from_millis(self.timeouts.lock().unwrap().command);
+
+        async_std::future::timeout(timeout, operation)
+            .await
+            .unwrap_or(Err(ZBBError::Timeout))
+    }
+
+    async fn run_with_timeout<T>(
+        &self,
+        id: &str,
+        timeout: Duration,
+        operation: impl Future<Output = Result<T, ZBBError>>,
+    ) -> Result<T, ZBBError> {
+        let registration = Registration {
+            cancellation: self
+                .cancellations
+                .lock()
+                .unwrap()
+                .entry(id.to_string())
+                .or_default()
+                .clone(),
+            operations: self,
+            id,
+        };
+
+        tokio::select! {
+            result = async_std::future::timeout(timeout, operation) => result.unwrap_or(Err(ZBBError::Timeout)),
+            _ = registration.cancellation.notified() => Err(ZBBError::Cancelled),
+        }
+    }
+
+    fn cancel(&self, id: &str) {
+        if let Some(cancellation) = self.cancellations.lock().unwrap().get(id) {
+            cancellation.notify_waiters();
+        }
+    }
+}
+
+/// Keeps the cancellation of [id] registered while an operation runs. The entry is
+/// removed with the last operation, also if the caller drops it halfway.
+struct Registration<'a> {
+    operations: &'a Operations,
+    id: &'a str,
+    cancellation: Arc<Notify>,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        let mut cancellations = self.operations.cancellations.lock().unwrap();
+
+        // One reference is held by the map, the other one by this registration
+        if Arc::strong_count(&self.cancellation) == 2 {
+            cancellations.remove(self.id);
+        }
+    }
+}
+
+/// Aborts all commands that are currently running for the device [id].
+#[tauri::command]
+pub async fn cancel_operations(id: String, operations: State<'_, Operations>) -> Result<(), ZBBError> {
+    info!("Cancelling operations of {}", id);
+    operations.cancel(&id);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_timeouts(operations: State<'_, Operations>) -> Result<Timeouts, ZBBError> {
+    Ok(operations.timeouts.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn set_timeouts(timeouts: Timeouts, operations: State<'_, Operations>) -> Result<(), ZBBError> {
+    if timeouts.command == 0 || timeouts.transfer == 0 {
+        return Err(ZBBError::InvalidArgument("Timeouts must be positive".into()));
+    }
+
+    *operations.timeouts.lock().unwrap() = timeouts;
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancellation_is_removed() {
+        let operations = Operations::new(Arc::new(Mutex::new(Timeouts::default())));
+
+        let result = operations.run("192.168.1.20", async { Ok(42) }).await;
+
+        assert_eq!(42, result.unwrap());
+        assert!(operations.cancellations.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use log::warn;
+use tauri::{AppHandle, State};
+use crate::adb::{emit_devices, Supervisor};
+use crate::structs::{LocalDevice, RegisteredDevice, ZBBError};
+use crate::util::{load_json, save_json};
+
+/// Friendly names, tags and notes of the headsets by serial, persisted as JSON
+/// in the app data dir. The last IP is taken from the [Supervisor].
+pub struct Registry {
+    path: Option<PathBuf>,
+    devices: Mutex<HashMap<String, RegisteredDevice>>,
+}
+
+impl Registry {
+    /// Loads the registry from [path], starting empty if there is none yet.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let devices = load_json(path.as_deref());
+
+        Self {
+            path,
+            devices: Mutex::new(devices),
+        }
+    }
+
+    /// Looks up the name of [identifier], which is either a serial or the `<ip>:<port>`
+    /// of a device connected over Wi-Fi.
+    pub fn name_of(&self, identifier: &str, supervisor: &Supervisor) -> Option<String> {
+        let serial = supervisor.find(identifier).map(|(serial, _)| serial);
+        let serial = serial.as_deref().unwrap_or(identifier);
+
+        self.devices.lock().unwrap().get(serial).and_then(|device| device.name.clone())
+    }
+
+    /// Fills in the names of [devices].
+    pub fn annotate(&self, devices: Vec<LocalDevice>, supervisor: &Supervisor) -> Vec<LocalDevice> {
+        devices
+            .into_iter()
+            .map(|device| LocalDevice {
+                name: self.name_of(&device.identifier, supervisor),
+                ..device
+            })
+            .collect()
+    }
+
+    fn save(&self, devices: &HashMap<String, RegisteredDevice>) {
+        let Some(path) = &self.path else { return };
+
+        if let Err(error) = save_json(path, devices) {
+            warn!("Unable to save device registry: {:?}", error);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_registered_devices<'a>(
+    registry: State<'a, Registry>,
+    supervisor: State<'a, Supervisor>,
+) -> Result<Vec<RegisteredDevice>, ZBBError> {
+    let mut devices = registry
+        .devices
+        .lock()
+        .unwrap()
+        .values()
+        .map(|device| RegisteredDevice {
+            last_ip: supervisor.find(&device.serial).map(|(_, address)| address.ip),
+            ..device.clone()
+        })
+        .collect::<Vec<_>>();
+    devices.sort_by(|a, b| a.serial.cmp(&b.serial));
+
+    Ok(devices)
+}
+
+/// Adds [device] to the registry or replaces the entry with the same serial. The
+/// `last_ip` is ignored, it is kept by the supervisor.
+#[tauri::command]
+pub async fn save_registered_device(
+    device: RegisteredDevice,
+    app: AppHandle,
+    registry: State<'_, Registry>,
+) -> Result<(), ZBBError> {
+    if device.serial.trim().is_empty() {
+        return Err(ZBBError::InvalidArgument("Missing serial".into()));
+    }
+
+    {
+        let mut devices = registry.devices.lock().unwrap();
+        devices.insert(device.serial.clone(), RegisteredDevice { last_ip: None, ..device });
+        registry.save(&devices);
+    }
+
+    // The device list shows the names
+    emit_devices(&app)
+}
+
+#[tauri::command]
+pub async fn remove_registered_device(
+    serial: String,
+    app: AppHandle,
+    registry: State<'_, Registry>,
+) -> Result<(), ZBBError> {
+    {
+        let mut devices = registry.devices.lock().unwrap();
+        devices.remove(&serial);
+        registry.save(&devices);
+    }
+
+    emit_devices(&app)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{KnownAddress, LocalDeviceState};
+
+    #[test]
+    fn test_name_of() {
+        let registry = Registry::load(None);
+        registry.devices.lock().unwrap().insert("1WMHH8000000".to_string(), RegisteredDevice {
+            serial: "1WMHH8000000".to_string(),
+            name: Some("Headset 3".to_string()),
+            tags: vec!["red".to_string()],
+            last_ip: None,
+            notes: None,
+        });
+        let supervisor = Supervisor::load(None);
+        supervisor.remember("1WMHH8000000".to_string(), KnownAddress {
+            ip: "192.168.1.20".to_string(),
+            port: 5555,
+        });
+
+        let devices = registry.annotate(vec![
+            LocalDevice {
+                identifier: "192.168.1.20:5555".to_string(),
+                state: LocalDeviceState::Device,
+                name: None,
+            },
+            LocalDevice {
+                identifier: "2G0YC1ZF".to_string(),
+                state: LocalDeviceState::Offline,
+                name: None,
+            },
+        ], &supervisor);
+
+        assert_eq!(Some("Headset 3"), registry.name_of("1WMHH8000000", &supervisor).as_deref());
+        assert_eq!(Some("Headset 3"), devices[0].name.as_deref());
+        assert_eq!(None, devices[1].name);
+    }
+}
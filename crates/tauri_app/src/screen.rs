@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::process::{Child, Stdio};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use image::{GenericImageView, ImageFormat, ImageOutputFormat};
+use log::{info, warn};
+use tauri::State;
+use crate::adb::ShellCommand;
+use crate::operations::Operations;
+use crate::client::AdbClient;
+use crate::structs::{Eye, ZBBError};
+use crate::util::sanitize_file_name;
+
+/// `screenrecord` stops by itself after 3 minutes at the latest.
+const MAX_TIME_LIMIT: u32 = 180;
+
+/// Running screen recordings by serial.
+#[derive(Default)]
+pub struct RecordingManager {
+    recordings: Mutex<HashMap<String, Recording>>,
+}
+
+struct Recording {
+    /// The `adb shell screenrecord` process on the host.
+    process: Child,
+    remote_path: String,
+    file_name: String,
+}
+
+/// Takes a screenshot of the device as PNG.
+///
+/// The Quest renders both eyes side by side, [eye] crops the image to one of them.
+/// [crop] is the `--crop` of the scrcpy settings (e.g. `2064:2208:2064:100`) and gives the
+/// size and offset of an eye, otherwise each eye is one half of the image.
+/// If [path] is given, the image is saved there and nothing is returned.
+#[tauri::command]
+pub async fn capture_screenshot<'a>(
+    id: String,
+    eye: Option<Eye>,
+    crop: Option<String>,
+    path: Option<String>,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+) -> Result<Option<Vec<u8>>, ZBBError> {
+    let serial = Some(id.clone());
+    let crop = crop.map(|crop| parse_crop(&crop)).transpose()?;
+
+    let mut png = operations
+        .run(&id, client.run(move |adb| adb.shell_command(&serial, ShellCommand::new("screencap").arg("-p").build())))
+        .await?;
+
+    if let Some(eye) = eye {
+        png = crop_to_eye(&png, eye, crop)?;
+    }
+
+    match path {
+        Some(path) => {
+            std::fs::write(path, png)?;
+            Ok(None)
+        }
+        None => Ok(Some(png)),
+    }
+}
+
+/// Starts recording the screen of the device.
+///
+/// [bit_rate] is given in bits per second, [time_limit] in seconds (at most 180).
+#[tauri::command]
+pub async fn start_recording<'a>(
+    id: String,
+    bit_rate: Option<u32>,
+    time_limit: Option<u32>,
+    client: State<'a, AdbClient>,
+    recordings: State<'a, RecordingManager>,
+) -> Result<(), ZBBError> {
+    if recordings.recordings.lock().unwrap().contains_key(&id) {
+        return Err(ZBBError::Other(format!("{} is already recording", id)));
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|it| it.as_secs())
+        .unwrap_or_default();
+    let file_name = format!("{}_{}.mp4", sanitize_file_name(&id), timestamp);
+    let remote_path = format!("/sdcard/{}", file_name);
+
+    let mut args = vec![
+        "-s".to_string(),
+        id.clone(),
+        "shell".to_string(),
+        "screenrecord".to_string(),
+        "--time-limit".to_string(),
+        time_limit.unwrap_or(MAX_TIME_LIMIT).min(MAX_TIME_LIMIT).to_string(),
+    ];
+    if let Some(bit_rate) = bit_rate {
+        args.push("--bit-rate".to_string());
+        args.push(bit_rate.to_string());
+    }
+    args.push(remote_path.clone());
+
+    let process = client
+        .command()?
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    info!("Recording {} to {}", id, remote_path);
+    recordings.recordings.lock().unwrap().insert(id, Recording {
+        process,
+        remote_path,
+        file_name,
+    });
+
+    Ok(())
+}
+
+/// Stops the recording, pulls it into [target_dir] and deletes it from the device.
+///
+/// Returns the path of the pulled file.
+#[tauri::command]
+pub async fn stop_recording<'a>(
+    id: String,
+    target_dir: String,
+    client: State<'a, AdbClient>,
+    operations: State<'a, Operations>,
+    recordings: State<'a, RecordingManager>,
+) -> Result<String, ZBBError> {
+    let Recording { mut process, remote_path, file_name } = recordings
+        .recordings
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .ok_or(ZBBError::Other(format!("{} is not recording", id)))?;
+    let serial = Some(id.clone());
+    let local_path = PathBuf::from(target_dir).join(file_name);
+
+    operations.run_transfer(&id, async {
+        // SIGINT lets screenrecord finalize the file. If the time limit is already up, there's nothing to stop.
+        let stop_serial = serial.clone();
+        let stopped = client.run(move |adb| adb.shell_command(&stop_serial, ShellCommand::new("pkill").arg("-INT").arg("screenrecord").build())).await;
+        if let Err(error) = stopped {
+            // Without the signal the local adb would wait for the time limit, the file stays unfinished
+            warn!("Unable to stop the recording on {}: {:?}", id, error);
+            let _ = process.kill();
+        }
+        tauri::async_runtime::spawn_blocking(move || process.wait())
+            .await
+            .map_err(|err| ZBBError::Other(err.to_string()))??;
+
+        client.run_adb(vec![
+            "-s".to_string(),
+            id.clone(),
+            "pull".to_string(),
+            remote_path.clone(),
+            local_path.to_string_lossy().to_string(),
+        ]).await?;
+
+        Ok(())
+    }).await?;
+
+    let rm = ShellCommand::new("rm").arg("-f").arg(&remote_path).build();
+    if let Err(error) = operations.run(&id, client.run(move |adb| adb.shell_command(&serial, rm.clone()))).await {
+        warn!("Unable to delete {} from {}: {:?}", remote_path, id, error);
+    }
+
+    Ok(local_path.to_string_lossy().to_string())
+}
+
+/// Area mirrored by scrcpy's `--crop=width:height:x:y`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Crop {
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+}
+
+fn parse_crop(crop: &str) -> Result<Crop, ZBBError> {
+    let invalid = || ZBBError::InvalidArgument(format!("Invalid crop: {}", crop));
+
+    let values = crop
+        .split(':')
+        .map(|value| value.trim().parse::<u32>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let [width, height, x, y] = values[..] else { return Err(invalid()) };
+
+    Ok(Crop { width, height, x, y })
+}
+
+/// Area of [eye] as (x, y, width, height), cut off at the border of the screenshot.
+///
+/// [crop] shows one of the eyes, the other one lies half the screenshot next to it.
+/// A crop wider than half the screenshot was made for another headset and is ignored.
+fn eye_area(eye: Eye, crop: Option<Crop>, width: u32, height: u32) -> (u32, u32, u32, u32) {
+    let half = width / 2;
+    let crop = crop
+        .filter(|crop| crop.width <= half)
+        .unwrap_or(Crop { width: half, height, x: 0, y: 0 });
+
+    let left = crop.x.checked_rem(half).unwrap_or(0);
+    let x = match eye {
+        Eye::Left => left,
+        Eye::Right => left + half,
+    }
+    .min(width);
+    let y = crop.y.min(height);
+
+    (x, y, crop.width.min(width - x), crop.height.min(height - y))
+}
+
+fn crop_to_eye(png: &[u8], eye: Eye, crop: Option<Crop>) -> Result<Vec<u8>, ZBBError> {
+    let image = image::load_from_memory_with_format(png, ImageFormat::Png)
+        .map_err(|err| ZBBError::Other(format!("Invalid screenshot: {:?}", err)))?;
+
+    let (width, height) = image.dimensions();
+    let (x, y, width, height) = eye_area(eye, crop, width, height);
+
+    let mut cropped = Vec::new();
+    image
+        .crop_imm(x, y, width, height)
+        .write_to(&mut Cursor::new(&mut cropped), ImageOutputFormat::Png)
+        .map_err(|err| ZBBError::Other(format!("Unable to encode screenshot: {:?}", err)))?;
+
+    Ok(cropped)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, Rgb, RgbImage};
+
+    #[test]
+    fn test_parse_crop() {
+        let crop = Crop { width: 2064, height: 2208, x: 2064, y: 100 };
+        assert_eq!(crop, parse_crop("2064:2208:2064:100").unwrap());
+
+        assert!(matches!(parse_crop("2064:2208:2064"), Err(ZBBError::InvalidArgument(_))));
+        assert!(matches!(parse_crop("2064:2208:x:100"), Err(ZBBError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_eye_area() {
+        // Quest 3 with the default scrcpy crop
+        let crop = Some(Crop { width: 2064, height: 2208, x: 2064, y: 100 });
+        assert_eq!((0, 100, 2064, 2108), eye_area(Eye::Left, crop, 4128, 2208));
+        assert_eq!((2064, 100, 2064, 2108), eye_area(Eye::Right, crop, 4128, 2208));
+
+        // Quest 2 without a crop
+        assert_eq!((0, 0, 1832, 1920), eye_area(Eye::Left, None, 3664, 1920));
+        assert_eq!((1832, 0, 1832, 1920), eye_area(Eye::Right, None, 3664, 1920));
+
+        // The Quest 3 crop doesn't fit a Quest 2
+        assert_eq!((1832, 0, 1832, 1920), eye_area(Eye::Right, crop, 3664, 1920));
+    }
+
+    #[test]
+    fn test_crop_to_eye() {
+        let image = RgbImage::from_fn(8, 4, |x, _| if x < 4 { Rgb([255, 0, 0]) } else { Rgb([0, 0, 255]) });
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(image)
+            .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+            .unwrap();
+
+        let right = image::load_from_memory(&crop_to_eye(&png, Eye::Right, None).unwrap()).unwrap();
+
+        assert_eq!((4, 4), right.dimensions());
+        assert_eq!([0, 0, 255], right.to_rgb8().get_pixel(0, 0).0);
+    }
+}
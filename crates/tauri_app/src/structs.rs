@@ -1,12 +1,12 @@
-use adb_client::{Device, DeviceState, RustADBError};
 use serde::{Deserialize, Serialize};
 use std::io;
 use std::io::Error;
-use std::net::AddrParseError;
+use std::net::{AddrParseError, Ipv4Addr};
 use std::string::FromUtf8Error;
 use strum_macros::{Display, EnumString};
+use crate::adb::{ADB_PORT, LOOPBACK};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum LocalDeviceState {
     /// The device is not connected to adb or is not responding.
     Offline,
@@ -20,14 +20,16 @@ pub enum LocalDeviceState {
     Unauthorized,
 }
 
-impl From<DeviceState> for LocalDeviceState {
-    fn from(state: DeviceState) -> Self {
+impl From<&str> for LocalDeviceState {
+    /// Parses the state column of the ADB host protocol. States we don't track
+    /// (recovery, sideload, bootloader, ...) are reported as offline.
+    fn from(state: &str) -> Self {
         match state {
-            DeviceState::Offline => LocalDeviceState::Offline,
-            DeviceState::Device => LocalDeviceState::Device,
-            DeviceState::NoDevice => LocalDeviceState::NoDevice,
-            DeviceState::Authorizing => LocalDeviceState::Authorizing,
-            DeviceState::Unauthorized => LocalDeviceState::Unauthorized,
+            "device" => LocalDeviceState::Device,
+            "no device" => LocalDeviceState::NoDevice,
+            "authorizing" => LocalDeviceState::Authorizing,
+            "unauthorized" => LocalDeviceState::Unauthorized,
+            _ => LocalDeviceState::Offline,
         }
     }
 }
@@ -50,21 +52,14 @@ pub struct LocalDeviceLong {
     pub transport_id: u32,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct LocalDevice {
     /// Unique device identifier.
     pub identifier: String,
     /// Connection state of the device.
     pub state: LocalDeviceState,
-}
-
-impl From<Device> for LocalDevice {
-    fn from(device: Device) -> Self {
-        LocalDevice {
-            identifier: device.identifier,
-            state: device.state.into(),
-        }
-    }
+    /// Friendly name from the device registry.
+    pub name: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -79,25 +74,67 @@ impl Paths {
     }
 }
 
+/// Endpoint of the ADB server, either the one launched locally or a server on another
+/// machine started with `adb -a nodaemon server`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AdbServer {
+    pub host: Ipv4Addr,
+    pub port: u16,
+}
+
+impl AdbServer {
+    /// Remote servers are managed by their machine, we never launch or relaunch them.
+    pub fn is_remote(&self) -> bool {
+        !self.host.is_loopback()
+    }
+
+    /// Arguments that point the adb binary to this server.
+    pub fn args(&self) -> Vec<String> {
+        vec![
+            "-H".to_string(),
+            self.host.to_string(),
+            "-P".to_string(),
+            self.port.to_string(),
+        ]
+    }
+}
+
+impl Default for AdbServer {
+    fn default() -> Self {
+        Self {
+            host: LOOPBACK,
+            port: ADB_PORT,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(tag = "type", content = "message")]
 pub enum ZBBError {
     ADB(String),
+    /// The ADB server can't be reached, even after relaunching it.
+    ServerUnavailable,
     IO(String),
     NotInANetwork,
     NotInSameNetwork,
+    /// An argument was rejected before it reached the device's shell.
+    InvalidArgument(String),
+    /// Failure reported by the package manager, e.g. `INSTALL_FAILED_VERSION_DOWNGRADE: ...`
+    PackageManager(String),
+    /// The command didn't finish within the configured [Timeouts].
+    Timeout,
+    /// The command was aborted with `cancel_operations`.
+    Cancelled,
     Other(String),
 }
 
-impl From<RustADBError> for ZBBError {
-    fn from(value: RustADBError) -> Self {
-        ZBBError::ADB(value.to_string())
-    }
-}
-
 impl From<io::Error> for ZBBError {
     fn from(value: Error) -> Self {
-        ZBBError::IO(value.to_string())
+        match value.kind() {
+            // Sockets with a read or write timeout report it as one of these
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => ZBBError::Timeout,
+            _ => ZBBError::IO(value.to_string()),
+        }
     }
 }
 
@@ -119,3 +156,349 @@ pub enum AppPhase {
     Station,
     Windup,
 }
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub enum RebootMode {
+    /// Full reboot of the device.
+    Normal,
+    /// Soft restart of the Android framework, the kernel and adbd keep running.
+    Framework,
+}
+
+/// How the phase channel reaches the app's socket.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum PhaseTransport {
+    /// Straight to the headset's IP, which has to be in the same network.
+    Network,
+    /// Through `adb forward` of the device [serial], which works over USB as well.
+    Forward { serial: String },
+}
+
+/// Emitted as `install-progress` while a package is installed or removed.
+#[derive(Serialize, Debug, Clone)]
+pub struct InstallProgress {
+    pub serial: String,
+    pub stage: InstallStage,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", content = "message")]
+pub enum InstallStage {
+    /// The APK is copied onto the device.
+    Uploading,
+    /// The package manager installs the uploaded APK.
+    Installing,
+    Uninstalling,
+    Finished,
+    Failed(String),
+}
+
+/// Emitted as `logcat-lines` with the lines a streamed device logged since the last batch.
+#[derive(Serialize, Debug, Clone)]
+pub struct LogcatLines {
+    pub serial: String,
+    pub lines: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+/// State of the headset battery as reported by `dumpsys battery`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct BatteryStatus {
+    /// Charge in percent.
+    pub level: i32,
+    pub charging: ChargingState,
+    /// Where the power comes from, `None` if the headset is unplugged.
+    pub power_source: Option<PowerSource>,
+    /// Temperature in °C.
+    pub temperature: f32,
+    pub health: BatteryHealth,
+    /// Voltage in mV.
+    pub voltage: i32,
+    /// Controllers paired with the headset.
+    pub controllers: Vec<ControllerStatus>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum ChargingState {
+    Unknown,
+    Charging,
+    Discharging,
+    NotCharging,
+    Full,
+}
+
+impl From<i32> for ChargingState {
+    /// Maps the `BatteryManager.BATTERY_STATUS_*` constants.
+    fn from(status: i32) -> Self {
+        match status {
+            2 => ChargingState::Charging,
+            3 => ChargingState::Discharging,
+            4 => ChargingState::NotCharging,
+            5 => ChargingState::Full,
+            _ => ChargingState::Unknown,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum PowerSource {
+    AC,
+    USB,
+    Wireless,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum BatteryHealth {
+    Unknown,
+    Good,
+    Overheat,
+    Dead,
+    OverVoltage,
+    Failure,
+    Cold,
+}
+
+impl From<i32> for BatteryHealth {
+    /// Maps the `BatteryManager.BATTERY_HEALTH_*` constants.
+    fn from(health: i32) -> Self {
+        match health {
+            2 => BatteryHealth::Good,
+            3 => BatteryHealth::Overheat,
+            4 => BatteryHealth::Dead,
+            5 => BatteryHealth::OverVoltage,
+            6 => BatteryHealth::Failure,
+            7 => BatteryHealth::Cold,
+            _ => BatteryHealth::Unknown,
+        }
+    }
+}
+
+/// Snapshot of the resources and the thermal state of a headset.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DeviceHealth {
+    /// Free space on `/data` in bytes.
+    pub storage_free: u64,
+    pub storage_total: u64,
+    /// Memory available to apps in bytes.
+    pub memory_free: u64,
+    pub memory_total: u64,
+    /// Overall thermal status of the device.
+    pub thermal_status: ThermalStatus,
+    /// Highest throttling severity reported by a single sensor.
+    pub throttling: ThermalStatus,
+    /// Hottest CPU sensor in °C, if the thermal HAL reports one.
+    pub cpu_temperature: Option<f32>,
+    /// Time since boot in seconds.
+    pub uptime: u64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum ThermalStatus {
+    None,
+    Light,
+    Moderate,
+    Severe,
+    Critical,
+    Emergency,
+    Shutdown,
+}
+
+impl From<i32> for ThermalStatus {
+    /// Maps the `PowerManager.THERMAL_STATUS_*` constants.
+    fn from(status: i32) -> Self {
+        match status {
+            1 => ThermalStatus::Light,
+            2 => ThermalStatus::Moderate,
+            3 => ThermalStatus::Severe,
+            4 => ThermalStatus::Critical,
+            5 => ThermalStatus::Emergency,
+            6 => ThermalStatus::Shutdown,
+            _ => ThermalStatus::None,
+        }
+    }
+}
+
+/// A controller paired with the headset, as reported by `dumpsys OVRRemoteService`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ControllerStatus {
+    pub hand: Hand,
+    /// Charge in percent, if the controller reports it.
+    pub level: Option<i32>,
+    pub connected: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum Hand {
+    Left,
+    Right,
+    Unknown,
+}
+
+/// A headset advertising ADB over Wi-Fi via mDNS.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DiscoveredDevice {
+    /// mDNS instance name, e.g. `adb-1WMHH8xxxx-AbCdEf`.
+    pub name: String,
+    pub service_type: String,
+    /// IP address to pass to `connect_to_ip`.
+    pub address: String,
+    pub port: u16,
+}
+
+/// Network address a device was last connected on.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KnownAddress {
+    pub ip: String,
+    pub port: u16,
+}
+
+impl KnownAddress {
+    /// Identifier of the TCP transport, e.g. `192.168.1.20:5555`.
+    pub fn identifier(&self) -> String {
+        format!("{}:{}", self.ip, self.port)
+    }
+}
+
+/// Emitted as `device-reconnect` for every attempt to reconnect a remembered device.
+#[derive(Serialize, Debug, Clone)]
+pub struct ReconnectAttempt {
+    pub serial: String,
+    /// `<ip>:<port>`, which is also the identifier of the device once it's connected.
+    pub address: String,
+    pub attempt: u32,
+    pub connected: bool,
+}
+
+/// An installed package as reported by `dumpsys package`.
+#[derive(Serialize, Debug, Clone)]
+pub struct PackageInfo {
+    pub name: String,
+    pub version_name: Option<String>,
+    pub version_code: Option<u64>,
+    /// Local time on the device, e.g. `2024-04-01 08:00:12`.
+    pub first_install_time: Option<String>,
+    pub last_update_time: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum AppState {
+    /// The app's activity is resumed, i.e. the participant sees it.
+    Foreground,
+    /// The process is alive, but another app is on screen.
+    Background,
+    Stopped,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AppActivity {
+    pub state: AppState,
+    /// The resumed activity as `<package>/<activity>`, if any.
+    pub resumed_activity: Option<String>,
+    pub foreground_package: Option<String>,
+}
+
+/// Deadlines of device commands in milliseconds.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Timeouts {
+    /// Shell commands, socket requests and connection attempts.
+    pub command: u64,
+    /// Installs, file transfers, recordings and waiting for a reboot.
+    pub transfer: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            command: 10_000,
+            transfer: 600_000,
+        }
+    }
+}
+
+/// A port forwarded with `adb forward` or `adb reverse`, e.g. `tcp:1337`.
+///
+/// For forwards, [local] is on the machine of the ADB server and [remote] on the device,
+/// for reverses it's the other way round.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PortForward {
+    pub serial: String,
+    pub local: String,
+    pub remote: String,
+}
+
+/// Details of a headset kept in the device registry, so operators don't have to
+/// memorise serials.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RegisteredDevice {
+    pub serial: String,
+    /// e.g. `Headset 3 – red strap`
+    pub name: Option<String>,
+    /// Groups the device belongs to.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// IP the device was last connected on over Wi-Fi.
+    pub last_ip: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Wi-Fi link of a headset as reported by `dumpsys wifi`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WifiStatus {
+    pub ssid: String,
+    /// MAC address of the access point.
+    pub bssid: String,
+    /// Signal strength in dBm.
+    pub rssi: i32,
+    /// Link speed in Mbps, if known.
+    pub link_speed: Option<i32>,
+    /// Frequency in MHz.
+    pub frequency: i32,
+    pub band: WifiBand,
+}
+
+/// Credentials of a Wi-Fi network to provision on headsets.
+#[derive(Deserialize, Debug, Clone)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    pub security: WifiSecurity,
+    /// Passphrase, not needed for open networks.
+    pub password: Option<String>,
+}
+
+/// Security types understood by `cmd wifi`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub enum WifiSecurity {
+    Open,
+    Owe,
+    Wpa2,
+    Wpa3,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum WifiBand {
+    #[serde(rename = "2.4GHz")]
+    Band2_4,
+    #[serde(rename = "5GHz")]
+    Band5,
+    #[serde(rename = "6GHz")]
+    Band6,
+    Unknown,
+}
+
+impl From<i32> for WifiBand {
+    /// Maps a frequency in MHz to its band.
+    fn from(frequency: i32) -> Self {
+        match frequency {
+            2400..=2500 => WifiBand::Band2_4,
+            4900..=5900 => WifiBand::Band5,
+            5925..=7125 => WifiBand::Band6,
+            _ => WifiBand::Unknown,
+        }
+    }
+}
@@ -1,11 +1,15 @@
 use std::ffi::OsStr;
 use std::net::{IpAddr, Ipv4Addr};
-use std::process::Command;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 use log::info;
 use network_interface::{NetworkInterface, NetworkInterfaceConfig};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use tauri::AppHandle;
 use which::which;
 use crate::structs::ZBBError;
@@ -24,6 +28,46 @@ pub fn create_silent_command<S>(path: S) -> Command where S: AsRef<OsStr> {
     Command::new(path)
 }
 
+/// Runs [command] like [Command::output], but kills it once [timeout] is up.
+///
+/// Only meant for commands with little output, the pipes aren't read before it exits.
+pub fn output_with_timeout(mut command: Command, timeout: Duration) -> Result<Output, ZBBError> {
+    let mut child = command.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let deadline = Instant::now() + timeout;
+
+    while child.try_wait()?.is_none() {
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(ZBBError::Timeout);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    Ok(child.wait_with_output()?)
+}
+
+/// Reads [path] as JSON, falling back to the default if it is missing or invalid.
+pub fn load_json<T>(path: Option<&Path>) -> T where T: DeserializeOwned + Default {
+    path.and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Writes [value] as JSON to [path], creating the missing directories.
+pub fn save_json<T>(path: &Path, value: &T) -> std::io::Result<()> where T: Serialize {
+    path.parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(path, serde_json::to_string_pretty(value)?))
+}
+
+/// Serials of network devices contain a `:`, which isn't allowed in file names on Windows.
+pub fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+        .collect()
+}
+
 
 pub fn find_binary(name: &str, handle: AppHandle, search_path: bool) -> Option<String> {
     info!("Looking for {}", name);
@@ -81,6 +125,38 @@ pub fn test_network(other: Ipv4Addr) -> Result<(), ZBBError> {
     Ok(())
 }
 
+/// Lists the IPv4 subnets of the host's network interfaces as `(address, netmask)`,
+/// skipping loopback interfaces.
+pub fn local_subnets() -> Result<Vec<(Ipv4Addr, Ipv4Addr)>, ZBBError> {
+    let network_interfaces =
+        NetworkInterface::show().map_err(|it| ZBBError::Other(it.to_string()))?;
+
+    Ok(network_interfaces
+        .into_iter()
+        .flat_map(|interface| interface.addr)
+        .filter_map(|addr| match (addr.ip(), addr.netmask()) {
+            (IpAddr::V4(ip), Some(IpAddr::V4(netmask))) if !ip.is_loopback() => Some((ip, netmask)),
+            _ => None,
+        })
+        .collect())
+}
+
+/// All host addresses in the subnet of [ip], excluding network, broadcast and [ip] itself.
+pub fn subnet_hosts(ip: Ipv4Addr, netmask: Ipv4Addr) -> Vec<Ipv4Addr> {
+    let mask = u32::from(netmask);
+    let network = u32::from(ip) & mask;
+    let broadcast = network | !mask;
+
+    if broadcast - network < 2 {
+        return vec![];
+    }
+
+    ((network + 1)..broadcast)
+        .map(Ipv4Addr::from)
+        .filter(|host| *host != ip)
+        .collect()
+}
+
 pub fn is_match(lhs: Ipv4Addr, rhs: Ipv4Addr, netmask: Ipv4Addr) -> bool {
     netmask.octets().into_iter().enumerate().all(|(pos, mask)| {
         lhs.octets()[pos] & mask == rhs.octets()[pos] & mask
@@ -104,4 +180,35 @@ mod tests {
         assert_eq!(false, is_match(ip1, ip3, netmask));
         assert_eq!(true, is_match(ip1, ip3, netmask2));
     }
+
+    #[test]
+    fn test_subnet_hosts() {
+        let ip = Ipv4Addr::new(192, 168, 1, 5);
+        let hosts = subnet_hosts(ip, Ipv4Addr::new(255, 255, 255, 0));
+
+        assert_eq!(253, hosts.len());
+        assert_eq!(Ipv4Addr::new(192, 168, 1, 1), hosts[0]);
+        assert_eq!(Ipv4Addr::new(192, 168, 1, 254), hosts[252]);
+        assert!(!hosts.contains(&ip));
+        assert!(subnet_hosts(ip, Ipv4Addr::new(255, 255, 255, 255)).is_empty());
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!("192.168.1.20_5555", sanitize_file_name("192.168.1.20:5555"));
+        assert_eq!("1WMHH8000000", sanitize_file_name("1WMHH8000000"));
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn test_output_with_timeout() {
+        let mut echo = Command::new("echo");
+        echo.arg("hello");
+        let output = output_with_timeout(echo, Duration::from_secs(5)).unwrap();
+        assert_eq!("hello\n", String::from_utf8_lossy(&output.stdout));
+
+        let mut sleep = Command::new("sleep");
+        sleep.arg("5");
+        assert!(matches!(output_with_timeout(sleep, Duration::from_millis(100)), Err(ZBBError::Timeout)));
+    }
 }
\ No newline at end of file
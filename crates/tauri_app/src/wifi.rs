@@ -0,0 +1,150 @@
+use log::info;
+use tauri::{AppHandle, Manager};
+use crate::adb::ShellCommand;
+use crate::batch::{run_batch, BatchResult};
+use crate::operations::Operations;
+use crate::client::AdbClient;
+use crate::structs::{WifiNetwork, WifiSecurity, ZBBError};
+
+/// Saves [network] on every headset in [ids] and connects them to it.
+///
+/// With [remove_others], all other saved networks are forgotten afterwards. Headsets that
+/// are connected to ADB over Wi-Fi drop off while they switch networks, so provisioning
+/// works best over USB.
+#[tauri::command]
+pub async fn provision_wifi(
+    ids: Vec<String>,
+    network: WifiNetwork,
+    remove_others: Option<bool>,
+    parallelism: Option<usize>,
+    app: AppHandle,
+) -> Result<BatchResult<()>, ZBBError> {
+    let connect = connect_command(&network)?;
+    let remove_others = remove_others.unwrap_or(false);
+
+    Ok(run_batch(ids, parallelism, app, move |app, id| {
+        let connect = connect.clone();
+        let ssid = network.ssid.clone();
+
+        async move {
+            let client = app.state::<AdbClient>();
+            let operations = app.state::<Operations>();
+
+            operations.run(&id, provision(&client, id.clone(), connect, ssid, remove_others)).await
+        }
+    }).await)
+}
+
+async fn provision(client: &AdbClient, id: String, connect: Vec<String>, ssid: String, remove_others: bool) -> Result<(), ZBBError> {
+    let serial = Some(id.clone());
+
+    let (result, removed) = client.run(move |adb| {
+        let result = String::from_utf8_lossy(&adb.shell_command(&serial, connect.clone())?).to_string();
+        if !remove_others || is_failure(&result) {
+            return Ok((result, vec![]));
+        }
+
+        let networks = adb.shell_command(&serial, ShellCommand::new("cmd").arg("wifi").arg("list-networks").build())?;
+        let mut removed = vec![];
+        for (network_id, other) in parse_network_list(&String::from_utf8_lossy(&networks)) {
+            if other != ssid {
+                adb.shell_command(&serial, ShellCommand::new("cmd").arg("wifi").arg("forget-network").arg(&network_id).build())?;
+                removed.push(other);
+            }
+        }
+
+        Ok((result, removed))
+    }).await?;
+
+    if is_failure(&result) {
+        return Err(ZBBError::ADB(result.trim().to_string()));
+    }
+
+    info!("Provisioned Wi-Fi on {}, removed networks: {:?}", id, removed);
+    Ok(())
+}
+
+/// Builds `cmd wifi connect-network <ssid> <security> [<password>]`, which saves the
+/// network and switches to it (Android 11+).
+fn connect_command(network: &WifiNetwork) -> Result<Vec<String>, ZBBError> {
+    if network.ssid.is_empty() || network.ssid.len() > 32 {
+        return Err(ZBBError::InvalidArgument(format!("Invalid SSID: {}", network.ssid)));
+    }
+
+    let command = ShellCommand::new("cmd").arg("wifi").arg("connect-network").arg(&network.ssid);
+
+    Ok(match network.security {
+        WifiSecurity::Open => command.arg("open"),
+        WifiSecurity::Owe => command.arg("owe"),
+        WifiSecurity::Wpa2 | WifiSecurity::Wpa3 => {
+            let password = network.password.as_deref().unwrap_or_default();
+            if !(8..=63).contains(&password.len()) {
+                return Err(ZBBError::InvalidArgument("The password must have 8 to 63 characters".into()));
+            }
+
+            let security = if network.security == WifiSecurity::Wpa2 { "wpa2" } else { "wpa3" };
+            command.arg(security).arg(password)
+        }
+    }.build())
+}
+
+/// `cmd` reports errors on stdout, e.g. `Unknown command: connect-network` on old Android versions.
+fn is_failure(output: &str) -> bool {
+    let output = output.trim();
+
+    output.starts_with("Error") || output.starts_with("Unknown command") || output.contains("Exception")
+}
+
+/// Parses the `<network id> <ssid> <security type>` lines of `cmd wifi list-networks`.
+fn parse_network_list(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let columns = line.split_whitespace().collect::<Vec<_>>();
+            let [network_id, ssid @ .., _security] = &columns[..] else { return None };
+
+            if ssid.is_empty() || network_id.parse::<u32>().is_err() {
+                return None;
+            }
+
+            Some((network_id.to_string(), ssid.join(" ").trim_matches('"').to_string()))
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_network_list() {
+        let output = "Network Id      SSID                         Security type
+0            \"ZBB Training\"                 wpa2-psk
+3            \"Old Site\"                     open
+";
+        let networks = parse_network_list(output);
+
+        assert_eq!(vec![
+            ("0".to_string(), "ZBB Training".to_string()),
+            ("3".to_string(), "Old Site".to_string()),
+        ], networks);
+    }
+
+    #[test]
+    fn test_connect_command() {
+        let network = WifiNetwork {
+            ssid: "ZBB Training".to_string(),
+            security: WifiSecurity::Wpa2,
+            password: Some("short".to_string()),
+        };
+        assert!(connect_command(&network).is_err());
+
+        let command = connect_command(&WifiNetwork {
+            password: Some("correct horse".to_string()),
+            ..network
+        }).unwrap();
+        assert_eq!(6, command.len());
+        assert_eq!("wpa2", command[4]);
+    }
+}
@@ -1,20 +1,38 @@
 import {Injectable} from '@angular/core';
-import {Observable, share, shareReplay, switchMap, timer} from "rxjs";
+import {catchError, EMPTY, merge, Observable, shareReplay} from "rxjs";
 import {invoke} from "@tauri-apps/api/tauri";
-import {Device} from "../domain/device.model";
+import {listen} from "@tauri-apps/api/event";
+import {Device, DeviceLong} from "../domain/device.model";
+import {BatteryStatus} from "../domain/battery.model";
+import {DeviceHealth} from "../domain/device-health.model";
+import {WifiNetwork, WifiStatus} from "../domain/wifi-status.model";
+import {ZBBError} from "../domain/zbberror.model";
+import {AdbServer} from "../domain/adb-server.model";
+import {RegisteredDevice} from "../domain/registered-device.model";
+import {AppActivity} from "../domain/app-activity.model";
 import {fromPromise} from "rxjs/internal/observable/innerFrom";
 
 @Injectable({
     providedIn: 'root'
 })
 export class DeviceService {
-    private devices$ = timer(1000, 500)
-        .pipe(
-            switchMap(() => {
-                return fromPromise(invoke<Device[]>('get_devices'))
-            }),
-            share()
-        )
+    private devices$ = merge(
+        // The events keep the list alive even if the first query fails
+        fromPromise(invoke<Device[]>('get_devices')).pipe(
+            catchError(e => {
+                console.error(e);
+                return EMPTY;
+            })
+        ),
+        new Observable<Device[]>(subscriber => {
+            const unlisten = listen<Device[]>('devices-changed', event => subscriber.next(event.payload));
+            return () => {
+                unlisten.then(fn => fn());
+            };
+        })
+    ).pipe(
+        shareReplay({bufferSize: 1, refCount: true})
+    )
 
     constructor() {
     }
@@ -23,6 +41,10 @@ export class DeviceService {
         return this.devices$;
     }
 
+    async getDevicesLong() {
+        return invoke<DeviceLong[]>('get_devices_long');
+    }
+
     async connect(id: string, port: number) {
         return await invoke<string>('connect_device', {
             id,
@@ -49,6 +71,13 @@ export class DeviceService {
         })
     }
 
+    async getAppState(id: string, packageName: string) {
+        return invoke<AppActivity>('get_app_state', {
+            id,
+            'package': packageName
+        })
+    }
+
 
     async isScreenOn(id: string) {
         return invoke<boolean>('is_screen_on', {
@@ -71,17 +100,63 @@ export class DeviceService {
     }
 
     async getBatteryLevel(id: string) {
-        return invoke<number>('get_battery_level', {
+        return invoke<BatteryStatus>('get_battery_level', {
             id
         }).catch(e => {
             return undefined;
         });
     }
 
+    getWifiStatus(id: string) {
+        return invoke<WifiStatus>('get_wifi_status', {
+            id
+        });
+    }
+
+    provisionWifi(ids: string[], network: WifiNetwork, removeOthers = false) {
+        return invoke<Record<string, { Ok: null } | { Err: ZBBError }>>('provision_wifi', {
+            ids,
+            network,
+            removeOthers
+        });
+    }
+
+    getDeviceHealth(id: string) {
+        return invoke<DeviceHealth>('get_device_health', {
+            id
+        });
+    }
+
     killServer() {
         return invoke('kill_server');
     }
 
+    getRegisteredDevices() {
+        return invoke<RegisteredDevice[]>('get_registered_devices');
+    }
+
+    saveRegisteredDevice(device: RegisteredDevice) {
+        return invoke<void>('save_registered_device', {
+            device
+        });
+    }
+
+    removeRegisteredDevice(serial: string) {
+        return invoke<void>('remove_registered_device', {
+            serial
+        });
+    }
+
+    getAdbServer() {
+        return invoke<AdbServer>('get_adb_server');
+    }
+
+    setAdbServer(server: AdbServer) {
+        return invoke<void>('set_adb_server', {
+            server
+        });
+    }
+
     attemptConnection(ip: string, port: number): Promise<void> {
         console.log(`attempt connection to ${ip}`);
         return invoke('connect_to_ip', {
@@ -96,6 +171,26 @@ export class DeviceService {
         });
     }
 
+    cancelOperations(id: string) {
+        return invoke<void>('cancel_operations', {
+            id
+        });
+    }
+
+    rebootDevice(id: string, mode: 'Normal' | 'Framework', wait = false) {
+        return invoke<void>('reboot_device', {
+            id,
+            mode,
+            wait
+        });
+    }
+
+    disconnectDevice(id: string) {
+        return invoke<void>('disconnect_device', {
+            id
+        });
+    }
+
     shutdownHost() {
         return invoke<void>('shutdown_host');
     }
@@ -22,6 +22,7 @@ import '@sbb-esta/lyne-elements/status.js';
 import {DeviceService} from "../device.service";
 import {
     asyncScheduler,
+    combineLatest,
     defer,
     distinctUntilChanged,
     filter,
@@ -36,7 +37,7 @@ import {
     timer
 } from "rxjs";
 import {Device, DeviceState} from "../../domain/device.model";
-import {takeUntilDestroyed} from "@angular/core/rxjs-interop";
+import {takeUntilDestroyed, toObservable} from "@angular/core/rxjs-interop";
 import {SbbStepperElement} from "@sbb-esta/lyne-elements/stepper.js";
 import {NgIf} from "@angular/common";
 import {ScrcpyService} from "../scrcpy.service";
@@ -104,7 +105,7 @@ export class DeviceComponent implements OnInit, OnDestroy {
         return 'battery-level-empty-small';
     });
     protected connectionError = signal<string | undefined>(undefined);
-    public isBusy = false;
+    public isBusy = signal(false);
 
     private _syncingSettings = true;
     private _scrcpyProcess?: Child;
@@ -142,10 +143,17 @@ export class DeviceComponent implements OnInit, OnDestroy {
         private _settingsService: SettingsService,
         private _notificationService: NotificationService
     ) {
-        this._deviceService.observeDevices().pipe(
+        // Map the last list again when the ip changes or a connection attempt is over,
+        // the list itself may not change anymore by then
+        combineLatest([
+            this._deviceService.observeDevices(),
+            toObservable(this.ip),
+            toObservable(this.port),
+            toObservable(this.isBusy)
+        ]).pipe(
             takeUntilDestroyed(),
-            filter(_ => !this.isBusy),
-            map(devices => this.extractDevices(devices)),
+            filter(([, , , isBusy]) => !isBusy),
+            map(([devices, ip, port]) => this.extractDevices(devices, ip, port)),
             distinctUntilChanged((lhs, rhs) => JSON.stringify(lhs) === JSON.stringify(rhs))
         ).subscribe(device => this.onDeviceChanged(device[0], device[1]));
 
@@ -163,6 +171,8 @@ export class DeviceComponent implements OnInit, OnDestroy {
                 const subscription = this.startConnecting(this.id(), this.port());
                 onCleanup(() => subscription.unsubscribe());
             }
+        }, {
+            allowSignalWrites: true
         });
 
         effect((onCleanup) => {
@@ -226,7 +236,7 @@ export class DeviceComponent implements OnInit, OnDestroy {
             const check = async () => {
                 try {
                     if (await this._deviceService.isScreenOn(this.ip()!) === true &&
-                        !await this._deviceService.isRunning(this.ip()!, this._settingsService.getCleanPackageName())) {
+                        (await this._deviceService.getAppState(this.ip()!, this._settingsService.getCleanPackageName())).state !== 'Foreground') {
                         console.log('launch')
 
                         await this._deviceService.launch(this.ip()!, this._settingsService.getPackageName());
@@ -257,8 +267,8 @@ export class DeviceComponent implements OnInit, OnDestroy {
 
             const subscription = timer(0, 30_000).pipe(
                 switchMap(_ => from(this._deviceService.getBatteryLevel(this.ip()!)))
-            ).subscribe(batteryLevel => {
-                this.batteryLevel.set(batteryLevel);
+            ).subscribe(batteryStatus => {
+                this.batteryLevel.set(batteryStatus?.level);
             });
 
             onCleanup(() => subscription.unsubscribe());
@@ -297,17 +307,15 @@ export class DeviceComponent implements OnInit, OnDestroy {
     private startConnecting(id: string, port: number): Subscription {
         // Try connecting until the subscription has been canceled or the connection has been established
         return defer(() => {
-            console.log('start connecting', this.isBusy);
+            console.log('start connecting');
 
-            this.isBusy = true;
+            this.isBusy.set(true);
             return from(this._deviceService.connect(id, port));
         }).pipe(
             tap({
-                next: () => this.isBusy = false,
-                error: (e) => {
-                    this.isBusy = false;
-                    this.handleError(e);
-                }
+                error: (e) => this.handleError(e),
+                // Also when the attempt is abandoned, otherwise the device list stays frozen
+                finalize: () => this.isBusy.set(false)
             }),
             retry({
                 delay: 1000
@@ -336,10 +344,10 @@ export class DeviceComponent implements OnInit, OnDestroy {
         }
     }
 
-    private extractDevices(devices: Device[]): [Device | undefined, Device | undefined] {
+    private extractDevices(devices: Device[], ip: string | undefined, port: number): [Device | undefined, Device | undefined] {
         return [
             devices.find(it => it.identifier === this.id()),
-            devices.find(it => it.identifier === `${this.ip()}:${this.port()}`)
+            devices.find(it => it.identifier === `${ip}:${port}`)
         ];
     }
 
@@ -1,22 +1,24 @@
 import {Injectable} from '@angular/core';
 import {invoke} from "@tauri-apps/api/tauri";
 import {defer, distinctUntilChanged, Observable, repeat} from "rxjs";
-import {Phase} from '../domain/phase.model';
+import {Phase, PhaseTransport} from '../domain/phase.model';
 
 @Injectable({
     providedIn: 'root'
 })
 export class PhaseService {
-    async setAppPhase(ip: string, phase: Phase) {
+    async setAppPhase(ip: string, phase: Phase, transport?: PhaseTransport) {
         return invoke<void>("set_phase", {
             ip,
-            phase
+            phase,
+            transport
         })
     }
 
-    async getAppPhase(ip: string): Promise<Phase> {
+    async getAppPhase(ip: string, transport?: PhaseTransport): Promise<Phase> {
         return invoke<Phase>('get_phase', {
-            ip
+            ip,
+            transport
         });
     }
 
@@ -0,0 +1,4 @@
+export interface AdbServer {
+    host: string;
+    port: number;
+}
@@ -0,0 +1,8 @@
+export interface AppActivity {
+    state: AppState,
+    /// The resumed activity as `<package>/<activity>`, if any.
+    resumed_activity: string | null,
+    foreground_package: string | null,
+}
+
+export type AppState = 'Foreground' | 'Background' | 'Stopped';
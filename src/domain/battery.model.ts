@@ -0,0 +1,27 @@
+export interface BatteryStatus {
+    /// Charge in percent.
+    level: number,
+    charging: ChargingState,
+    /// Where the power comes from, null if the headset is unplugged.
+    power_source: PowerSource | null,
+    /// Temperature in °C.
+    temperature: number,
+    health: BatteryHealth,
+    /// Voltage in mV.
+    voltage: number,
+    /// Controllers paired with the headset.
+    controllers: ControllerStatus[],
+}
+
+export interface ControllerStatus {
+    hand: 'Left' | 'Right' | 'Unknown',
+    /// Charge in percent, if the controller reports it.
+    level: number | null,
+    connected: boolean,
+}
+
+export type ChargingState = 'Unknown' | 'Charging' | 'Discharging' | 'NotCharging' | 'Full';
+
+export type PowerSource = 'AC' | 'USB' | 'Wireless';
+
+export type BatteryHealth = 'Unknown' | 'Good' | 'Overheat' | 'Dead' | 'OverVoltage' | 'Failure' | 'Cold';
@@ -0,0 +1,17 @@
+export interface DeviceHealth {
+    /// Free space on `/data` in bytes.
+    storage_free: number,
+    storage_total: number,
+    /// Memory available to apps in bytes.
+    memory_free: number,
+    memory_total: number,
+    thermal_status: ThermalStatus,
+    /// Highest throttling severity reported by a single sensor.
+    throttling: ThermalStatus,
+    /// Hottest CPU sensor in °C.
+    cpu_temperature: number | null,
+    /// Time since boot in seconds.
+    uptime: number,
+}
+
+export type ThermalStatus = 'None' | 'Light' | 'Moderate' | 'Severe' | 'Critical' | 'Emergency' | 'Shutdown';
@@ -2,7 +2,9 @@ export interface Device {
     /// Unique device identifier.
     identifier: string,
         /// Connection state of the device.
-    state: DeviceState
+    state: DeviceState,
+    /// Friendly name from the device registry.
+    name: string | null
 }
 
 export interface DeviceLong {
@@ -2,4 +2,5 @@ export enum Phase {
     Onboarding = 'Onboarding',
     Station = 'Station',
     Windup = 'Windup'
-}
\ No newline at end of file
+}
+export type PhaseTransport = { type: 'Network' } | { type: 'Forward', serial: string };
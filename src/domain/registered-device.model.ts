@@ -0,0 +1,9 @@
+export interface RegisteredDevice {
+    serial: string,
+    /// e.g. `Headset 3 – red strap`
+    name: string | null,
+    tags: string[],
+    /// IP the device was last connected on over Wi-Fi.
+    last_ip: string | null,
+    notes: string | null,
+}
@@ -0,0 +1,21 @@
+export interface WifiStatus {
+    ssid: string,
+    /// MAC address of the access point.
+    bssid: string,
+    /// Signal strength in dBm.
+    rssi: number,
+    /// Link speed in Mbps.
+    link_speed: number | null,
+    /// Frequency in MHz.
+    frequency: number,
+    band: WifiBand,
+}
+
+export type WifiBand = '2.4GHz' | '5GHz' | '6GHz' | 'Unknown';
+
+export interface WifiNetwork {
+    ssid: string,
+    security: 'Open' | 'Owe' | 'Wpa2' | 'Wpa3',
+    /// Passphrase, not needed for open networks.
+    password?: string,
+}
@@ -1,4 +1,4 @@
-export type ZBBError = NotInANetwork | NotInSameNetwork | ADBError | IO | Other;
+export type ZBBError = NotInANetwork | NotInSameNetwork | InvalidArgument | ADBError | ServerUnavailable | PackageManager | Timeout | Cancelled | IO | Other;
 
 type NotInANetwork = {
     type: 'NotInANetwork'
@@ -8,11 +8,33 @@ type NotInSameNetwork = {
     type: 'NotInSameNetwork'
 }
 
+type InvalidArgument = {
+    type: 'InvalidArgument',
+    message: string
+}
+
 type ADBError = {
     type: 'ADB',
     message: string
 }
 
+type ServerUnavailable = {
+    type: 'ServerUnavailable'
+}
+
+type PackageManager = {
+    type: 'PackageManager',
+    message: string
+}
+
+type Timeout = {
+    type: 'Timeout'
+}
+
+type Cancelled = {
+    type: 'Cancelled'
+}
+
 type IO = {
     type: 'IO',
     message: string